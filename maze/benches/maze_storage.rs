@@ -0,0 +1,115 @@
+//! Memory and speed of the wall storage for large mazes.
+//!
+//! Run with `cargo bench --bench maze_storage`. The packed `Maze` storage is
+//! compared against the previous layout of one `Vec<bool>` per column.
+
+use std::hint::black_box;
+use std::mem::size_of;
+use std::time::{Duration, Instant};
+
+use maze::maze::wall::Direction::*;
+use maze::maze::{Maze, Wall};
+
+const SIZES: [usize; 3] = [100, 1000, 2000];
+
+struct NestedStorage {
+    vertical_walls: Vec<Vec<bool>>,
+    horizontal_walls: Vec<Vec<bool>>,
+}
+
+impl NestedStorage {
+    fn new(width: usize, height: usize) -> NestedStorage {
+        NestedStorage {
+            vertical_walls: vec![vec![true; height]; width + 1],
+            horizontal_walls: vec![vec![true; height + 1]; width],
+        }
+    }
+
+    fn heap_size(&self) -> usize {
+        let columns = self.vertical_walls.iter().chain(self.horizontal_walls.iter());
+        let column_bytes: usize = columns.map(|column| column.capacity()).sum();
+
+        column_bytes
+            + (self.vertical_walls.capacity() + self.horizontal_walls.capacity())
+                * size_of::<Vec<bool>>()
+    }
+
+    fn set_wall(&mut self, wall: &Wall, on: bool) {
+        match *wall {
+            Wall { x, y, dir: Up } => self.horizontal_walls[x][y] = on,
+            Wall { x, y, dir: Down } => self.horizontal_walls[x][y + 1] = on,
+            Wall { x, y, dir: Left } => self.vertical_walls[x][y] = on,
+            Wall { x, y, dir: Right } => self.vertical_walls[x + 1][y] = on,
+        }
+    }
+
+    fn get_wall(&self, wall: &Wall) -> bool {
+        match *wall {
+            Wall { x, y, dir: Up } => self.horizontal_walls[x][y],
+            Wall { x, y, dir: Down } => self.horizontal_walls[x][y + 1],
+            Wall { x, y, dir: Left } => self.vertical_walls[x][y],
+            Wall { x, y, dir: Right } => self.vertical_walls[x + 1][y],
+        }
+    }
+}
+
+fn time<F: FnMut()>(mut f: F) -> Duration {
+    let start = Instant::now();
+    f();
+    start.elapsed()
+}
+
+fn carve_walls(size: usize) -> impl Iterator<Item = Wall> {
+    itertools::iproduct!(0..size, 0..size).map(move |(x, y)| Wall {
+        x,
+        y,
+        dir: if (x + y) % 2 == 0 { Right } else { Down },
+    })
+}
+
+fn bench_packed(size: usize) {
+    let mut maze = Maze::new(size, size, true);
+
+    let set = time(|| {
+        for wall in carve_walls(size) {
+            maze.set_wall(&wall, false);
+        }
+    });
+    let get = time(|| {
+        let on = maze.wall_iter().filter(|wall| maze.get_wall(wall)).count();
+        black_box(on);
+    });
+
+    report("packed", size, maze.wall_storage_size(), set, get);
+}
+
+fn bench_nested(size: usize) {
+    let maze = Maze::new(size, size, true);
+    let mut storage = NestedStorage::new(size, size);
+
+    let set = time(|| {
+        for wall in carve_walls(size) {
+            storage.set_wall(&wall, false);
+        }
+    });
+    let get = time(|| {
+        let on = maze.wall_iter().filter(|wall| storage.get_wall(wall)).count();
+        black_box(on);
+    });
+
+    report("nested", size, storage.heap_size(), set, get);
+}
+
+fn report(name: &str, size: usize, bytes: usize, set: Duration, get: Duration) {
+    println!(
+        "{:>6} {:>4}x{:<4} {:>10} bytes  set {:>8.2?}  get {:>8.2?}",
+        name, size, size, bytes, set, get
+    );
+}
+
+fn main() {
+    for size in SIZES {
+        bench_nested(size);
+        bench_packed(size);
+    }
+}
@@ -0,0 +1,98 @@
+use nannou::color::IntoLinSrgba;
+use nannou::prelude::*;
+
+use crate::canvas::Canvas;
+
+use super::Animator;
+
+/// Plays a scene fading in from a plain colour, then holds its last frame
+/// and fades it out to the same colour.
+pub struct FadeAnimator {
+    animator: Box<dyn Animator>,
+    color: Rgb8,
+    fade_frames: u32,
+    hold_frames: u32,
+    frame: u32,
+    // Frames since the scene was done
+    frames_after: u32,
+}
+
+impl FadeAnimator {
+    /// Fades take `fade_frames` each, and the last frame of the scene is
+    /// held for `hold_frames` before fading out.
+    pub fn new(
+        animator: Box<dyn Animator>,
+        color: Rgb8,
+        fade_frames: u32,
+        hold_frames: u32,
+    ) -> Self {
+        FadeAnimator {
+            animator,
+            color,
+            fade_frames,
+            hold_frames,
+            frame: 0,
+            frames_after: 0,
+        }
+    }
+
+    /// How much the colour covers the scene, from 0 to 1.
+    fn opacity(&self) -> f32 {
+        let fade = self.fade_frames.max(1) as f32;
+        let fade_in = 1.0 - self.frame as f32 / fade;
+        let fade_out = self.frames_after.saturating_sub(self.hold_frames) as f32 / fade;
+
+        fade_in.max(fade_out).clamp(0.0, 1.0)
+    }
+}
+
+impl Animator for FadeAnimator {
+    fn update(&mut self) {
+        self.frame += 1;
+
+        if self.animator.done() {
+            self.frames_after += 1;
+        } else {
+            self.animator.update();
+        }
+    }
+
+    fn draw(&self, draw: &dyn Canvas, window: &Rect) {
+        self.animator.draw(draw, window);
+
+        let opacity = self.opacity();
+        if opacity > 0.0 {
+            let mut color = self.color.into_lin_srgba();
+            color.alpha = opacity;
+            draw.rect(*window, color);
+        }
+    }
+
+    fn done(&self) -> bool {
+        self.animator.done() && self.frames_after >= self.hold_frames + self.fade_frames
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animate::WaitingAnimator;
+
+    #[test]
+    fn fade_animator_holds_the_scene_then_fades_it_out() {
+        let scene = Box::new(WaitingAnimator::new(2));
+        let mut animator = FadeAnimator::new(scene, rgb8(0, 0, 0), 4, 3);
+
+        assert_eq!(animator.opacity(), 1.0);
+
+        let mut frames = 0;
+        while !animator.done() {
+            animator.update();
+            frames += 1;
+        }
+
+        // 2 frames of the scene, 3 of holding it and 4 of fading out
+        assert_eq!(frames, 9);
+        assert_eq!(animator.opacity(), 1.0);
+    }
+}
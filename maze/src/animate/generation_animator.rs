@@ -1,21 +1,63 @@
+use nannou::color::IntoLinSrgba;
 use nannou::prelude::*;
 
+use crate::canvas::Canvas;
 use crate::draw::Draw as MazeDraw;
-use crate::{generate::MazeGenerator, maze::Maze};
+use crate::generate::{Highlight, MazeGenerator};
+use crate::maze::Topology;
 
-use super::Animator;
+use super::speed::{estimate_steps, Pacer};
+use super::{Animator, GenerationSpeed, SolutionStyle};
 
 pub struct AnimatorConfig {
     pub back_color: Rgb8,
     pub wall_color: Rgb8,
+    pub highlight_color: Rgb8,
+    pub accent_color: Rgb8,
+    /// Style of the solution drawn once the maze is generated.
+    pub solution: SolutionStyle,
+    /// Colour of the generator name under the maze.
+    pub text_color: Rgb8,
     pub wall_size: f32,
     pub y: f32,
+    pub speed: GenerationSpeed,
 }
 
-pub struct MazeGenerationAnimator<T> {
+impl Default for AnimatorConfig {
+    fn default() -> Self {
+        AnimatorConfig {
+            back_color: rgb8(0x07, 0x10, 0x13),
+            wall_color: rgb8(0x01, 0x97, 0xf6),
+            highlight_color: rgb8(0x2e, 0x6b, 0x3a),
+            accent_color: rgb8(0xe0, 0x9f, 0x3e),
+            solution: SolutionStyle::default(),
+            text_color: rgb8(0xcc, 0xc4, 0xbc),
+            wall_size: 32.0,
+            y: 8.0,
+            speed: GenerationSpeed::default(),
+        }
+    }
+}
+
+// Space kept free around a fitted maze, for the label under it
+const FIT_MARGIN: f32 = 32.0;
+
+impl AnimatorConfig {
+    /// Config with walls shrunk from `wall_size` as much as needed for a
+    /// maze of `size` to fit in a frame of `frame` pixels.
+    pub fn fitted(self, size: Vec2, frame: Vec2) -> Self {
+        let fit = (frame - 2.0 * FIT_MARGIN) / size;
+        let wall_size = self.wall_size.min(fit.min_element()).max(1.0);
+
+        AnimatorConfig { wall_size, ..self }
+    }
+}
+
+pub struct MazeGenerationAnimator<T: MazeGenerator> {
     config: AnimatorConfig,
     generator: T,
-    maze: Maze,
+    maze: T::Maze,
+    pacer: Pacer,
     first_frame: bool,
     pub maze_completed: bool,
 }
@@ -24,18 +66,24 @@ impl<T> Animator for MazeGenerationAnimator<T>
 where
     T: MazeGenerator,
 {
-    fn update(&mut self) -> () {
-        if !self.first_frame {
-            self.handle_new_wall();
-        } else {
+    fn update(&mut self) {
+        if self.first_frame {
             self.first_frame = false;
+            return;
+        }
+
+        for _ in 0..self.pacer.next_frame() {
+            if self.maze_completed {
+                break;
+            }
+            self.handle_new_wall();
         }
     }
 
-    fn draw(&self, draw: &Draw, _window: &Rect) -> () {
-        let draw = draw.x_y(0.0, self.config.y);
+    fn draw(&self, draw: &dyn Canvas, _window: &Rect) {
+        let draw = draw.translate(vec2(0.0, self.config.y));
 
-        draw.background().color(self.config.back_color);
+        draw.background(self.config.back_color.into_lin_srgba());
 
         let maze_draw = MazeDraw::new(
             &draw,
@@ -44,7 +92,12 @@ where
             self.config.wall_size,
         );
 
-        for wall in self.maze.wall_iter() {
+        if !self.maze_completed {
+            self.draw_regions(&maze_draw);
+            self.draw_highlights(&maze_draw);
+        }
+
+        for wall in self.maze.walls() {
             maze_draw.wall(&wall);
         }
     }
@@ -60,25 +113,29 @@ where
 {
     pub fn new(config: AnimatorConfig, generator: T) -> MazeGenerationAnimator<T> {
         let maze = generator.initial_maze();
+        let steps = generator.remaining_steps().unwrap_or_else(|| estimate_steps(&maze));
+        let pacer = Pacer::new(config.speed, steps);
         MazeGenerationAnimator {
             config,
             generator,
             maze,
+            pacer,
             first_frame: true,
             maze_completed: false,
         }
     }
 
-    pub fn top_left_cell(&self) -> Vec2 {
-        let x =
-            -(self.maze.width() as f32 * self.config.wall_size / 2.0) + self.config.wall_size / 2.0;
-        let y = (self.maze.height() as f32 * self.config.wall_size / 2.0)
-            - self.config.wall_size / 2.0
-            + self.config.y;
-        pt2(x, y)
+    /// Position of the centre of `cell` in the window.
+    pub fn cell_position(&self, cell: <T::Maze as Topology>::Cell) -> Vec2 {
+        self.maze_position(self.maze.cell_center(cell))
+    }
+
+    /// Position in the window of a point in maze geometry.
+    pub fn maze_position(&self, point: Vec2) -> Vec2 {
+        point * self.config.wall_size + vec2(0.0, self.config.y)
     }
 
-    pub fn get_maze(&self) -> Option<&Maze> {
+    pub fn get_maze(&self) -> Option<&T::Maze> {
         if self.maze_completed {
             Some(&self.maze)
         } else {
@@ -86,6 +143,41 @@ where
         }
     }
 
+    fn draw_regions(&self, maze_draw: &MazeDraw<T::Maze>) {
+        for cell in self.maze.cells() {
+            if let Some(region) = self.generator.region(cell) {
+                maze_draw.cell(cell, region_color(region));
+            }
+        }
+    }
+
+    fn draw_highlights(&self, maze_draw: &MazeDraw<T::Maze>) {
+        let highlights = match self.generator.state() {
+            Some(state) => state.highlights(),
+            None => return,
+        };
+
+        let color = |alpha: u8| {
+            let Rgb8 { red, green, blue, .. } = self.config.highlight_color;
+            rgba8(red, green, blue, alpha)
+        };
+
+        for highlight in highlights {
+            match highlight {
+                Highlight::Field { top_left, bottom_right } => {
+                    maze_draw.field(top_left, bottom_right, color(0x30))
+                }
+                Highlight::Stack(cells) => {
+                    for cell in cells {
+                        maze_draw.cell(cell, color(0x70));
+                    }
+                }
+                Highlight::ActiveCell(cell) => maze_draw.cell(cell, color(0xff)),
+                Highlight::Gap(cell) => maze_draw.cell(cell, self.config.accent_color),
+            }
+        }
+    }
+
     fn handle_new_wall(&mut self) {
         if let Some((wall, state)) = self.generator.next() {
             self.maze.set_wall(&wall, state);
@@ -94,3 +186,30 @@ where
         }
     }
 }
+
+/// Distinct, muted colour for each region label. Consecutive labels are
+/// spread around the colour wheel by the golden ratio.
+fn region_color(region: usize) -> Hsl {
+    let hue = (region as f32 * 0.618_034).fract();
+    hsl(hue, 0.45, 0.25)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fitted_config_only_shrinks_mazes_too_big_for_the_frame() {
+        let frame = vec2(1280.0, 720.0);
+        let default = AnimatorConfig::default();
+
+        let small = AnimatorConfig::default().fitted(vec2(38.0, 20.0), frame);
+        let wide = AnimatorConfig::default().fitted(vec2(100.0, 20.0), frame);
+        let tall = AnimatorConfig::default().fitted(vec2(10.0, 60.0), frame);
+
+        assert_eq!(small.wall_size, default.wall_size);
+        assert!(wide.wall_size * 100.0 <= frame.x - 2.0 * FIT_MARGIN);
+        assert!(tall.wall_size * 60.0 <= frame.y - 2.0 * FIT_MARGIN);
+        assert!(tall.wall_size < wide.wall_size);
+    }
+}
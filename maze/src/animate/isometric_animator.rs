@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use nannou::color::IntoLinSrgba;
+use nannou::prelude::*;
+
+use crate::canvas::Canvas;
+use crate::generate::MazeGenerator;
+use crate::isometric::{IsometricConfig, IsometricDraw};
+use crate::maze::Topology;
+
+use super::speed::{estimate_steps, Pacer};
+use super::{Animator, GenerationSpeed};
+
+pub struct IsometricAnimatorConfig {
+    pub back_color: Rgb8,
+    pub maze: IsometricConfig,
+    /// Fraction of the full height a wall rises or sinks by each frame, or
+    /// `None` for walls that appear and disappear at once.
+    pub rise_speed: Option<f32>,
+    pub y: f32,
+    pub speed: GenerationSpeed,
+}
+
+impl Default for IsometricAnimatorConfig {
+    fn default() -> Self {
+        IsometricAnimatorConfig {
+            back_color: rgb8(0x07, 0x10, 0x13),
+            maze: IsometricConfig {
+                floor_color: rgb8(0x10, 0x22, 0x28),
+                wall_color: rgb8(0x01, 0x97, 0xf6),
+                cell_size: 20.0,
+                wall_height: 0.6,
+                wall_thickness: 0.15,
+            },
+            rise_speed: Some(0.08),
+            y: -40.0,
+            speed: GenerationSpeed::default(),
+        }
+    }
+}
+
+// Space kept free around a fitted maze
+const FIT_MARGIN: f32 = 24.0;
+
+impl IsometricAnimatorConfig {
+    /// Config with cells shrunk from `cell_size` as much as needed for a
+    /// maze of `size` to fit in a frame of `frame` pixels.
+    pub fn fitted(mut self, size: Vec2, frame: Vec2) -> Self {
+        // The maze is turned by 45 degrees, so its width and height both
+        // spread along each axis, and its walls rise above its back
+        let (sin, cos) = deg_to_rad(30.0).sin_cos();
+        let half_span = (size.x + size.y) / 2.0;
+        let room = frame / 2.0 - FIT_MARGIN;
+
+        let fit = [
+            room.x / (half_span * cos),
+            (room.y - self.y) / (half_span * sin + self.maze.wall_height),
+            (room.y + self.y) / (half_span * sin),
+        ];
+        let fit = fit.into_iter().fold(self.maze.cell_size, f32::min);
+
+        self.maze.cell_size = fit.max(1.0);
+        self
+    }
+}
+
+/// Shows a maze being generated in the isometric view, with walls rising
+/// and sinking as they are turned on and off.
+pub struct IsometricAnimator<T: MazeGenerator> {
+    config: IsometricAnimatorConfig,
+    generator: T,
+    maze: T::Maze,
+    // Current height of each wall that is not settled yet
+    moving_walls: HashMap<<T::Maze as Topology>::Wall, f32>,
+    pacer: Pacer,
+    generation_done: bool,
+}
+
+impl<T> IsometricAnimator<T>
+where
+    T: MazeGenerator,
+    <T::Maze as Topology>::Wall: Eq + Hash,
+{
+    pub fn new(config: IsometricAnimatorConfig, generator: T) -> Self {
+        let maze = generator.initial_maze();
+        let steps = generator.remaining_steps().unwrap_or_else(|| estimate_steps(&maze));
+        let pacer = Pacer::new(config.speed, steps);
+
+        IsometricAnimator {
+            config,
+            generator,
+            maze,
+            moving_walls: HashMap::new(),
+            pacer,
+            generation_done: false,
+        }
+    }
+
+    /// Height of `wall` as a fraction of the full height.
+    fn raised(&self, wall: &<T::Maze as Topology>::Wall) -> f32 {
+        match self.moving_walls.get(wall) {
+            Some(&raised) => raised,
+            None if self.maze.get_wall(wall) => 1.0,
+            None => 0.0,
+        }
+    }
+
+    fn handle_new_wall(&mut self) {
+        if let Some((wall, on)) = self.generator.next() {
+            if self.config.rise_speed.is_some() {
+                let raised = self.raised(&wall);
+                self.moving_walls.insert(wall, raised);
+            }
+            self.maze.set_wall(&wall, on);
+        } else {
+            self.generation_done = true;
+        }
+    }
+
+    fn move_walls(&mut self, speed: f32) {
+        let maze = &self.maze;
+
+        self.moving_walls.retain(|wall, raised| {
+            let target = if maze.get_wall(wall) { 1.0 } else { 0.0 };
+            let left = target - *raised;
+
+            *raised += left.clamp(-speed, speed);
+            left.abs() > speed
+        });
+    }
+}
+
+impl<T> Animator for IsometricAnimator<T>
+where
+    T: MazeGenerator,
+    <T::Maze as Topology>::Wall: Eq + Hash,
+{
+    fn update(&mut self) {
+        if let Some(speed) = self.config.rise_speed {
+            self.move_walls(speed);
+        }
+
+        for _ in 0..self.pacer.next_frame() {
+            if self.generation_done {
+                break;
+            }
+            self.handle_new_wall();
+        }
+    }
+
+    fn draw(&self, draw: &dyn Canvas, _window: &Rect) {
+        let draw = draw.translate(vec2(0.0, self.config.y));
+        draw.background(self.config.back_color.into_lin_srgba());
+
+        let iso = IsometricDraw::new(&draw, &self.maze, &self.config.maze);
+        iso.floor();
+        iso.walls(|wall| self.raised(wall));
+    }
+
+    fn done(&self) -> bool {
+        self.generation_done && self.moving_walls.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fitted_config_only_shrinks_mazes_too_big_for_the_frame() {
+        let frame = vec2(1280.0, 720.0);
+        let default = IsometricAnimatorConfig::default().maze.cell_size;
+
+        let small = IsometricAnimatorConfig::default().fitted(vec2(38.0, 20.0), frame);
+        let big = IsometricAnimatorConfig::default().fitted(vec2(60.0, 40.0), frame);
+
+        assert_eq!(small.maze.cell_size, default);
+        assert!(big.maze.cell_size < default);
+        // The front corner stays above the bottom of the frame
+        let front = 50.0 * deg_to_rad(30.0).sin() * big.maze.cell_size - big.y;
+        assert!(front <= frame.y / 2.0);
+    }
+}
@@ -1,4 +1,8 @@
+use crate::canvas::Canvas;
 use crate::generate::MazeGenerator;
+use crate::maze::Topology;
+use crate::solve::{path_lines, solve};
+use nannou::color::IntoLinSrgba;
 use nannou::prelude::*;
 
 use super::{
@@ -7,50 +11,99 @@ use super::{
     Animator,
 };
 
+type Cell<T> = <<T as MazeGenerator>::Maze as Topology>::Cell;
+
 pub struct MazeAnimator<T>
 where
     T: MazeGenerator,
 {
     generation_animator: MazeGenerationAnimator<T>,
-    solution_animator: MazeSolutionAnimator,
-    generator_name: String,
+    solution_animator: Option<MazeSolutionAnimator>,
+    generator_name: Option<String>,
+    text_color: Rgb8,
     begin_counter: u32,
+    endpoints: Option<(Cell<T>, Cell<T>)>,
 }
 
 impl<T> MazeAnimator<T>
 where
     T: MazeGenerator,
 {
-    pub fn new(generator: T, start: (usize, usize), end: (usize, usize)) -> Self
-    where
-        T: MazeGenerator,
-    {
-        let config = AnimatorConfig {
-            back_color: rgb8(0x07, 0x10, 0x13),
-            wall_color: rgb8(0x01, 0x97, 0xf6),
-            wall_size: 32.0,
-            y: 8.0,
-        };
-        let begin_counter = 48;
+    pub fn new(generator: T, start: Cell<T>, end: Cell<T>) -> Self {
+        Self::with_config(AnimatorConfig::default(), generator, start, end)
+    }
+
+    pub fn with_config(config: AnimatorConfig, generator: T, start: Cell<T>, end: Cell<T>) -> Self {
         let generator_name = generator.name();
-        let generation_animator = MazeGenerationAnimator::new(config, generator);
+        let style = config.solution;
+        let mut animator = Self::title_with_config(config, generator);
 
         let config = SolutionAnimatorConfig {
-            wall_size: 32.0,
-            dot_size: 18.0,
-            top_left: generation_animator.top_left_cell(),
-            start,
-            end,
-            color: rgb(0xa5, 0x24, 0x22),
-            line_weight: 5.0,
+            start: animator.generation_animator.cell_position(start),
+            end: animator.generation_animator.cell_position(end),
+            style,
         };
-        let solution_animator = MazeSolutionAnimator::new(config);
+
+        animator.solution_animator = Some(MazeSolutionAnimator::new(config));
+        animator.generator_name = Some(generator_name);
+        animator.endpoints = Some((start, end));
+        animator
+    }
+
+    /// Animator that only generates the maze, without solving it or
+    /// showing the generator name, e.g. for a maze shaped like a title.
+    pub fn title(generator: T) -> Self {
+        Self::title_with_config(AnimatorConfig::default(), generator)
+    }
+
+    pub fn title_with_config(config: AnimatorConfig, generator: T) -> Self {
+        let begin_counter = 48;
+        let text_color = config.text_color;
+        let generation_animator = MazeGenerationAnimator::new(config, generator);
 
         Self {
             generation_animator,
-            solution_animator,
-            generator_name,
+            solution_animator: None,
+            generator_name: None,
+            text_color,
             begin_counter,
+            endpoints: None,
+        }
+    }
+
+    /// Shows `label` under the maze instead of the generator name, or
+    /// nothing if it is `None`.
+    pub fn with_label(mut self, label: Option<String>) -> Self {
+        self.generator_name = label;
+        self
+    }
+
+    fn solve(&mut self) {
+        let (start, end) = match self.endpoints {
+            Some(endpoints) => endpoints,
+            None => return,
+        };
+
+        let maze = self.generation_animator.get_maze().unwrap();
+        let path = match solve(maze, start, end) {
+            Some(path) => path,
+            // Nothing to show when the end can't be reached
+            None => {
+                self.solution_animator = None;
+                return;
+            }
+        };
+        let parts = path_lines(maze, &path)
+            .into_iter()
+            .map(|part| {
+                part.into_iter()
+                    .map(|point| self.generation_animator.maze_position(point))
+                    .collect()
+            })
+            .collect();
+
+        if let Some(solution_animator) = self.solution_animator.as_mut() {
+            solution_animator.set_solution(parts);
         }
     }
 }
@@ -65,30 +118,42 @@ where
             return;
         }
 
-        if self.generation_animator.done() {
-            self.solution_animator
-                .set_maze(self.generation_animator.get_maze().unwrap());
-        }
-
         if !self.generation_animator.done() {
             self.generation_animator.update();
-        } else {
-            self.solution_animator.update();
+            return;
+        }
+
+        if self.solution_animator.as_ref().is_some_and(|solution| !solution.has_solution()) {
+            self.solve();
+        }
+
+        if let Some(solution_animator) = self.solution_animator.as_mut() {
+            solution_animator.update();
         }
     }
 
-    fn draw(&self, draw: &Draw, window: &Rect) {
+    fn draw(&self, draw: &dyn Canvas, window: &Rect) {
         self.generation_animator.draw(draw, window);
-        self.solution_animator.draw(draw, window);
 
-        draw.text(&self.generator_name)
-            .xy(window.pad_bottom(30.0).mid_bottom())
-            .w(window.w())
-            .color(rgb8(0xcc, 0xc4, 0xbc))
-            .font_size(30);
+        if let Some(solution_animator) = &self.solution_animator {
+            solution_animator.draw(draw, window);
+        }
+
+        if let Some(generator_name) = &self.generator_name {
+            draw.text(
+                generator_name,
+                window.pad_bottom(30.0).mid_bottom(),
+                window.w(),
+                30,
+                self.text_color.into_lin_srgba(),
+            );
+        }
     }
 
     fn done(&self) -> bool {
-        self.solution_animator.done()
+        match &self.solution_animator {
+            Some(solution_animator) => solution_animator.done(),
+            None => self.generation_animator.done(),
+        }
     }
 }
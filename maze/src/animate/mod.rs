@@ -1,5 +1,8 @@
 mod generation_animator;
+pub use generation_animator::AnimatorConfig;
+
 mod solution_animator;
+pub use solution_animator::{SolutionAnimatorConfig, SolutionStyle};
 
 mod maze_animator;
 pub use maze_animator::*;
@@ -7,10 +10,24 @@ pub use maze_animator::*;
 mod waiting_animator;
 pub use waiting_animator::*;
 
+mod isometric_animator;
+pub use isometric_animator::*;
+
+mod fade_animator;
+pub use fade_animator::*;
+
+mod sequence_animator;
+pub use sequence_animator::*;
+
+mod speed;
+pub use speed::GenerationSpeed;
+
 use nannou::prelude::*;
 
+use crate::canvas::Canvas;
+
 pub trait Animator {
     fn update(&mut self);
-    fn draw(&self, draw: &Draw, window: &Rect);
+    fn draw(&self, draw: &dyn Canvas, window: &Rect);
     fn done(&self) -> bool;
 }
\ No newline at end of file
@@ -0,0 +1,68 @@
+use std::collections::VecDeque;
+
+use nannou::prelude::*;
+
+use crate::canvas::Canvas;
+
+use super::Animator;
+
+/// Plays animators one after the other as a single one, e.g. to record all
+/// the scenes of a video at once.
+pub struct SequenceAnimator {
+    animators: VecDeque<Box<dyn Animator>>,
+    started: bool,
+}
+
+impl SequenceAnimator {
+    pub fn new(animators: VecDeque<Box<dyn Animator>>) -> Self {
+        SequenceAnimator { animators, started: false }
+    }
+}
+
+impl Animator for SequenceAnimator {
+    fn update(&mut self) {
+        // Every animator gets at least one frame, even if it starts done
+        let current_done = self.animators.front().is_some_and(|animator| animator.done());
+        if self.started && current_done && self.animators.len() > 1 {
+            self.animators.pop_front();
+        }
+
+        if let Some(animator) = self.animators.front_mut() {
+            animator.update();
+        }
+        self.started = true;
+    }
+
+    fn draw(&self, draw: &dyn Canvas, window: &Rect) {
+        if let Some(animator) = self.animators.front() {
+            animator.draw(draw, window);
+        }
+    }
+
+    fn done(&self) -> bool {
+        self.animators.len() <= 1 && self.animators.front().is_none_or(|animator| animator.done())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animate::WaitingAnimator;
+
+    #[test]
+    fn sequence_plays_every_animator_in_turn() {
+        let animators: VecDeque<Box<dyn Animator>> = [2, 0, 3]
+            .into_iter()
+            .map(|frames| Box::new(WaitingAnimator::new(frames)) as Box<dyn Animator>)
+            .collect();
+        let mut sequence = SequenceAnimator::new(animators);
+
+        let mut frames = 0;
+        while !sequence.done() {
+            sequence.update();
+            frames += 1;
+        }
+
+        assert_eq!(frames, 2 + 1 + 3);
+    }
+}
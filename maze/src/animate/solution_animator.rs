@@ -1,24 +1,73 @@
+use std::iter;
+
+use nannou::color::IntoLinSrgba;
 use nannou::prelude::*;
 
-use crate::{maze::Maze, solve::solve};
+use crate::canvas::Canvas;
 
 use super::Animator;
 
-pub struct SolutionAnimatorConfig {
-    pub wall_size: f32,
-    pub dot_size: f32,
-    pub top_left: Vec2,
-    pub start: (usize, usize),
-    pub end: (usize, usize),
+/// How the solution looks and moves, wherever it goes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SolutionStyle {
     pub color: Rgb8,
     pub line_weight: f32,
+    pub dot_size: f32,
+    /// Points of the path drawn each frame, e.g. 0.5 for one every 2 frames.
+    pub draw_speed: f32,
+    /// Points the dot moves along the path each frame once it is drawn.
+    pub erase_speed: f32,
+    /// Leaves the path behind the dot on screen instead of erasing it.
+    pub keep_path: bool,
+    /// Slides the dot and the ends of the path between points instead of
+    /// jumping from one to the next.
+    pub smooth: bool,
+    /// Colour the oldest end of the path fades from, into `color` at the
+    /// newest end. The path is a single colour without it.
+    pub trail_color: Option<Rgb8>,
+}
+
+impl Default for SolutionStyle {
+    fn default() -> Self {
+        SolutionStyle {
+            color: rgb8(0xa5, 0x24, 0x22),
+            line_weight: 5.0,
+            dot_size: 18.0,
+            draw_speed: 1.0,
+            erase_speed: 1.0,
+            keep_path: false,
+            smooth: false,
+            trail_color: None,
+        }
+    }
+}
+
+pub struct SolutionAnimatorConfig {
+    pub start: Vec2,
+    pub end: Vec2,
+    pub style: SolutionStyle,
+}
+
+impl SolutionAnimatorConfig {
+    /// Config with the default style for a path from `start` to `end`.
+    pub fn new(start: Vec2, end: Vec2) -> Self {
+        SolutionAnimatorConfig {
+            start,
+            end,
+            style: SolutionStyle::default(),
+        }
+    }
 }
 
 pub struct MazeSolutionAnimator {
     config: SolutionAnimatorConfig,
-    solution: Option<Vec<(usize, usize)>>,
-    lines_to_draw: usize,
-    lines_to_skip: usize,
+    solution: Option<Vec<Vec2>>,
+    // Indices of the points that start a new part of the path
+    breaks: Vec<usize>,
+    // Positions along the path, in points, of the end drawn so far and of
+    // the dot following it
+    head: f32,
+    tail: f32,
     done: bool,
 }
 
@@ -26,80 +75,205 @@ impl MazeSolutionAnimator {
     pub fn new(config: SolutionAnimatorConfig) -> Self {
         MazeSolutionAnimator {
             solution: None,
+            breaks: Vec::new(),
             config,
-            lines_to_draw: 0,
-            lines_to_skip: 0,
+            head: 0.0,
+            tail: 0.0,
             done: false,
         }
     }
 
-    fn maze_pos_to_xy_pos(&self, (x, y): (usize, usize)) -> Vec2 {
-        self.config.top_left + pt2(x as f32, -(y as f32)) * self.config.wall_size
+    /// Sets the path to animate, as the positions along it from start to
+    /// end. The path is split in parts where it jumps from one place to
+    /// another, e.g. across the edges of a wrapping maze.
+    pub fn set_solution(&mut self, parts: Vec<Vec<Vec2>>) {
+        let mut solution = Vec::new();
+        self.breaks.clear();
+
+        for part in parts {
+            if !solution.is_empty() {
+                self.breaks.push(solution.len());
+            }
+            solution.extend(part);
+        }
+
+        self.solution = Some(solution);
+    }
+
+    pub fn has_solution(&self) -> bool {
+        self.solution.is_some()
+    }
+
+    /// Position `along` the path, rounded down to a point unless the
+    /// style is smooth.
+    fn position(&self, solution: &[Vec2], along: f32) -> Vec2 {
+        let along = if self.config.style.smooth { along } else { along.floor() };
+        let index = (along as usize).min(solution.len() - 1);
+        let next = index + 1;
+
+        if next >= solution.len() || self.breaks.contains(&next) {
+            return solution[index];
+        }
+        solution[index].lerp(solution[next], along - index as f32)
     }
 
-    pub fn set_maze(&mut self, maze: &Maze) {
-        self.solution = Some(solve(maze, self.config.start, self.config.end));
+    /// Position of the dot, at the start until the path is drawn.
+    fn dot_position(&self) -> Vec2 {
+        match self.solution.as_deref() {
+            Some([]) => self.config.end,
+            Some(solution) => self.position(solution, self.tail),
+            None => self.config.start,
+        }
+    }
+
+    /// Draws the path between `from` and `to`, split at the breaks.
+    fn draw_path(&self, draw: &dyn Canvas, solution: &[Vec2], from: f32, to: f32) {
+        let style = &self.config.style;
+        let starts = iter::once(0).chain(self.breaks.iter().copied());
+        let ends = self.breaks.iter().copied().chain(iter::once(solution.len()));
+
+        for (start, end) in starts.zip(ends) {
+            let first = from.max(start as f32);
+            let last = to.min((end - 1) as f32);
+            if first >= last {
+                continue;
+            }
+
+            // Positions along the path of the points of the line
+            let inner = (first.floor() as usize + 1..end).map(|i| i as f32);
+            let mut alongs: Vec<f32> = iter::once(first)
+                .chain(inner.take_while(|&along| along < last))
+                .chain(iter::once(last))
+                .collect();
+            alongs.dedup();
+            let points: Vec<Vec2> =
+                alongs.iter().map(|&along| self.position(solution, along)).collect();
+
+            let trail_color = match style.trail_color {
+                Some(color) => color,
+                None => {
+                    draw.polyline(&points, style.line_weight, style.color.into_lin_srgba());
+                    continue;
+                }
+            };
+
+            for i in 1..points.len() {
+                let middle = (alongs[i - 1] + alongs[i]) / 2.0;
+                let newness = (middle - from) / (to - from);
+                let color = mix(trail_color, style.color, newness);
+                draw.polyline(&points[i - 1..=i], style.line_weight, color.into_lin_srgba());
+            }
+        }
     }
 }
 
 impl Animator for MazeSolutionAnimator {
     fn update(&mut self) {
-        if self.solution.is_none() || self.done() {
-            return;
-        }
+        let solution_len = match &self.solution {
+            Some(solution) if !self.done => solution.len(),
+            _ => return,
+        };
 
-        let solution_len = self.solution.as_ref().unwrap().len();
+        let style = &self.config.style;
+        let last = solution_len.saturating_sub(1) as f32;
 
-        if self.lines_to_skip > solution_len {
+        if self.head < last {
+            self.head = (self.head + style.draw_speed).min(last);
+        } else if self.tail < last {
+            self.tail = (self.tail + style.erase_speed).min(last);
+        } else {
             self.done = true;
-            return;
         }
+    }
 
-        if self.lines_to_draw <= solution_len {
-            self.lines_to_draw += 1;
-        } else if self.lines_to_skip <= solution_len {
-            self.lines_to_skip += 1;
+    fn draw(&self, draw: &dyn Canvas, _window: &Rect) {
+        let style = &self.config.style;
+
+        if let Some(solution) = self.solution.as_deref().filter(|solution| !solution.is_empty()) {
+            let from = if style.keep_path { 0.0 } else { self.tail };
+            self.draw_path(draw, solution, from, self.head);
         }
+
+        let size = vec2(style.dot_size, style.dot_size);
+        draw.ellipse(self.dot_position(), size, style.color.into_lin_srgba());
     }
 
-    fn draw(&self, draw: &Draw, _window: &Rect) {
-        if let Some(solution) = self.solution.as_ref() {
-            let points = solution
-                .iter()
-                .map(|&(x, y)| self.maze_pos_to_xy_pos((x, y)))
-                .skip(self.lines_to_skip)
-                .take(self.lines_to_draw)
-                .peekable();
-
-            if points.len() > 0 {
-                draw.polyline()
-                    .color(self.config.color)
-                    .weight(self.config.line_weight)
-                    .caps_round()
-                    .points(points);
-            }
-        }
+    fn done(&self) -> bool {
+        self.done
+    }
+}
 
-        let start = if self.solution.is_none() {
-            self.maze_pos_to_xy_pos(self.config.start)
-        } else {
-            let cell = self
-                .solution
-                .as_ref()
-                .unwrap()
-                .get(self.lines_to_skip)
-                .unwrap_or(&self.config.end);
-
-            self.maze_pos_to_xy_pos(*cell)
+/// Colour `amount` of the way from `from` to `to`.
+fn mix(from: Rgb8, to: Rgb8, amount: f32) -> Rgb8 {
+    let amount = amount.clamp(0.0, 1.0);
+    let channel = |from: u8, to: u8| {
+        (from as f32 + (to as f32 - from as f32) * amount).round() as u8
+    };
+
+    rgb8(channel(from.red, to.red), channel(from.green, to.green), channel(from.blue, to.blue))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn animator(style: SolutionStyle) -> MazeSolutionAnimator {
+        let config = SolutionAnimatorConfig {
+            style,
+            ..SolutionAnimatorConfig::new(Vec2::ZERO, Vec2::ZERO)
         };
+        let mut animator = MazeSolutionAnimator::new(config);
 
-        draw.ellipse()
-            .w_h(self.config.dot_size, self.config.dot_size)
-            .color(self.config.color)
-            .xy(start);
+        let points = (0..=8).map(|x| vec2(x as f32, 0.0)).collect();
+        animator.set_solution(vec![points]);
+        animator
     }
 
-    fn done(&self) -> bool {
-        self.done
+    fn frames_taken(animator: &mut MazeSolutionAnimator) -> usize {
+        let mut frames = 0;
+        while !animator.done() {
+            animator.update();
+            frames += 1;
+        }
+        frames
+    }
+
+    #[test]
+    fn solution_is_drawn_and_erased_at_its_speeds() {
+        let default = frames_taken(&mut animator(SolutionStyle::default()));
+        let fast = frames_taken(&mut animator(SolutionStyle {
+            draw_speed: 2.0,
+            erase_speed: 4.0,
+            ..Default::default()
+        }));
+        let slow = frames_taken(&mut animator(SolutionStyle {
+            draw_speed: 0.5,
+            ..Default::default()
+        }));
+
+        assert_eq!(default, 8 + 8 + 1);
+        assert_eq!(fast, 4 + 2 + 1);
+        assert_eq!(slow, 16 + 8 + 1);
+    }
+
+    #[test]
+    fn smooth_dot_slides_between_points() {
+        let style = SolutionStyle { erase_speed: 0.25, ..Default::default() };
+        let mut stepped = animator(style);
+        let mut smooth = animator(SolutionStyle { smooth: true, ..style });
+
+        for _ in 0..8 + 3 {
+            stepped.update();
+            smooth.update();
+        }
+
+        assert_eq!(stepped.dot_position(), Vec2::ZERO);
+        assert_eq!(smooth.dot_position(), vec2(0.75, 0.0));
+    }
+
+    #[test]
+    fn mix_blends_each_channel() {
+        let color = mix(rgb8(0, 100, 255), rgb8(100, 100, 55), 0.25);
+        assert_eq!(color, rgb8(25, 100, 205));
     }
 }
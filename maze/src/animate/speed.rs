@@ -0,0 +1,163 @@
+use crate::maze::Topology;
+
+/// How fast a maze is generated.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GenerationSpeed {
+    /// Steps taken each frame at full speed, e.g. 0.25 for a step every 4
+    /// frames.
+    pub steps_per_frame: f32,
+    /// Frames the whole generation should take instead, whatever the size
+    /// of the maze. This is only about right for generators that don't know
+    /// their `remaining_steps`.
+    pub duration: Option<u32>,
+    /// Fraction of the generation spent speeding up at the start, and
+    /// slowing down at the end, from 0 to 0.5.
+    pub ramp: f32,
+}
+
+impl Default for GenerationSpeed {
+    fn default() -> Self {
+        GenerationSpeed {
+            steps_per_frame: 1.0,
+            duration: None,
+            ramp: 0.0,
+        }
+    }
+}
+
+// Speed at the very start and end of a ramp, relative to the full speed
+const RAMP_FLOOR: f32 = 0.1;
+
+impl GenerationSpeed {
+    /// Speed relative to the full speed, `progress` of the way through the
+    /// generation.
+    fn factor(&self, progress: f32) -> f32 {
+        if self.ramp <= 0.0 {
+            return 1.0;
+        }
+
+        let ramp = self.ramp.min(0.5);
+        let edge = progress.min(1.0 - progress).max(0.0);
+        (edge / ramp).clamp(RAMP_FLOOR, 1.0)
+    }
+
+    /// Frames taken per step on average, relative to the full speed.
+    fn slowdown(&self) -> f32 {
+        let samples = 100;
+        let total: f32 = (0..samples)
+            .map(|i| 1.0 / self.factor((i as f32 + 0.5) / samples as f32))
+            .sum();
+
+        total / samples as f32
+    }
+}
+
+/// Number of steps a generator takes on `maze`, if it ends up with a
+/// perfect maze and changes one wall at each step. Used for generators
+/// that don't know their `remaining_steps`.
+pub(crate) fn estimate_steps<M: Topology>(maze: &M) -> usize {
+    let open = maze.walls().filter(|wall| !maze.get_wall(wall)).count();
+    let passages = maze.cells().count().saturating_sub(1);
+
+    open.abs_diff(passages).max(1)
+}
+
+/// Hands out the steps to take each frame at a `GenerationSpeed`.
+pub(crate) struct Pacer {
+    speed: GenerationSpeed,
+    steps_per_frame: f32,
+    step_estimate: usize,
+    steps_taken: usize,
+    // Fraction of a step carried over to the next frame
+    budget: f32,
+}
+
+impl Pacer {
+    pub fn new(speed: GenerationSpeed, step_estimate: usize) -> Self {
+        let steps_per_frame = match speed.duration {
+            Some(frames) => step_estimate as f32 * speed.slowdown() / frames.max(1) as f32,
+            None => speed.steps_per_frame,
+        };
+        // Nothing is gained from taking more than the whole generation in a
+        // frame, and huge budgets don't fit in the step counts
+        let steps_per_frame = steps_per_frame.min(step_estimate.max(1) as f32);
+
+        Pacer {
+            speed,
+            steps_per_frame,
+            step_estimate,
+            steps_taken: 0,
+            budget: 0.0,
+        }
+    }
+
+    /// Steps to take this frame.
+    pub fn next_frame(&mut self) -> usize {
+        let progress = self.steps_taken as f32 / self.step_estimate as f32;
+        self.budget += self.steps_per_frame * self.speed.factor(progress.min(1.0));
+
+        let steps = self.budget.floor();
+        self.budget -= steps;
+        self.steps_taken = self.steps_taken.saturating_add(steps as usize);
+        steps as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::Maze;
+
+    fn frames_taken(speed: GenerationSpeed, steps: usize) -> usize {
+        let mut pacer = Pacer::new(speed, steps);
+        let mut taken = 0;
+        let mut frames = 0;
+
+        while taken < steps {
+            taken += pacer.next_frame();
+            frames += 1;
+        }
+        frames
+    }
+
+    #[test]
+    fn pacer_takes_whole_and_fractional_steps() {
+        let speed = |steps_per_frame| GenerationSpeed { steps_per_frame, ..Default::default() };
+
+        assert_eq!(frames_taken(speed(1.0), 100), 100);
+        assert_eq!(frames_taken(speed(4.0), 100), 25);
+        assert_eq!(frames_taken(speed(0.25), 100), 400);
+    }
+
+    #[test]
+    fn pacer_fits_ramps_in_the_duration() {
+        let speed = GenerationSpeed { duration: Some(120), ramp: 0.3, ..Default::default() };
+        let frames = frames_taken(speed, 1000);
+
+        assert!((115..=125).contains(&frames), "took {} frames", frames);
+
+        let mut pacer = Pacer::new(speed, 1000);
+        let start = pacer.next_frame();
+        let middle = (0..60).map(|_| pacer.next_frame()).last().unwrap();
+        assert!(start < middle);
+    }
+
+    #[test]
+    fn pacer_takes_at_most_every_step_in_a_frame() {
+        for steps_per_frame in [1e30, f32::INFINITY] {
+            let speed = GenerationSpeed { steps_per_frame, ..Default::default() };
+            let mut pacer = Pacer::new(speed, 100);
+
+            for _ in 0..10 {
+                assert_eq!(pacer.next_frame(), 100);
+            }
+        }
+    }
+
+    #[test]
+    fn estimate_steps_counts_passages_of_a_perfect_maze() {
+        assert_eq!(estimate_steps(&Maze::new(4, 3, true)), 11);
+        // Starting without walls, all but the passages are built
+        assert_eq!(estimate_steps(&Maze::new(4, 3, false)), 31 - 11);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::canvas::Canvas;
+
 use super::Animator;
 
 pub struct WaitingAnimator {
@@ -17,7 +19,7 @@ impl Animator for WaitingAnimator {
         self.frames_left = self.frames_left.saturating_sub(1);
     }
 
-    fn draw(&self, _draw: &nannou::Draw, _window: &nannou::prelude::Rect) {}
+    fn draw(&self, _draw: &dyn Canvas, _window: &nannou::prelude::Rect) {}
 
     fn done(&self) -> bool {
         self.frames_left == 0
@@ -0,0 +1,109 @@
+use nannou::color::LinSrgba;
+use nannou::prelude::*;
+
+/// Surface the animators draw on, with the origin in the middle and `y`
+/// pointing up. It is implemented for `nannou::Draw`, to draw on the GPU,
+/// and for `Rasterizer`, to draw on the CPU.
+pub trait Canvas {
+    fn background(&self, color: LinSrgba);
+
+    /// Line through `points`, with round caps and joins.
+    fn polyline(&self, points: &[Vec2], weight: f32, color: LinSrgba);
+
+    fn polygon(&self, points: &[Vec2], color: LinSrgba);
+
+    /// Ellipse of `size` around `center`.
+    fn ellipse(&self, center: Vec2, size: Vec2, color: LinSrgba);
+
+    fn rect(&self, rect: Rect, color: LinSrgba);
+
+    /// Line of text centred on `center`, within `width`.
+    fn text(&self, text: &str, center: Vec2, width: f32, font_size: u32, color: LinSrgba);
+}
+
+impl<'a> dyn Canvas + 'a {
+    /// Canvas drawing on this one, with everything moved by `offset`.
+    pub fn translate(&self, offset: Vec2) -> Translate<'_> {
+        Translate { canvas: self, offset }
+    }
+}
+
+pub struct Translate<'a> {
+    canvas: &'a dyn Canvas,
+    offset: Vec2,
+}
+
+impl<'a> Translate<'a> {
+    fn moved(&self, points: &[Vec2]) -> Vec<Vec2> {
+        points.iter().map(|&point| point + self.offset).collect()
+    }
+}
+
+impl<'a> Canvas for Translate<'a> {
+    fn background(&self, color: LinSrgba) {
+        self.canvas.background(color);
+    }
+
+    fn polyline(&self, points: &[Vec2], weight: f32, color: LinSrgba) {
+        self.canvas.polyline(&self.moved(points), weight, color);
+    }
+
+    fn polygon(&self, points: &[Vec2], color: LinSrgba) {
+        self.canvas.polygon(&self.moved(points), color);
+    }
+
+    fn ellipse(&self, center: Vec2, size: Vec2, color: LinSrgba) {
+        self.canvas.ellipse(center + self.offset, size, color);
+    }
+
+    fn rect(&self, rect: Rect, color: LinSrgba) {
+        self.canvas.rect(rect.shift(self.offset), color);
+    }
+
+    fn text(&self, text: &str, center: Vec2, width: f32, font_size: u32, color: LinSrgba) {
+        self.canvas.text(text, center + self.offset, width, font_size, color);
+    }
+}
+
+impl Canvas for nannou::Draw {
+    fn background(&self, color: LinSrgba) {
+        nannou::Draw::background(self).color(color);
+    }
+
+    fn polyline(&self, points: &[Vec2], weight: f32, color: LinSrgba) {
+        nannou::Draw::polyline(self)
+            .color(color)
+            .caps_round()
+            .join_round()
+            .weight(weight)
+            .points(points.iter().copied());
+    }
+
+    fn polygon(&self, points: &[Vec2], color: LinSrgba) {
+        nannou::Draw::polygon(self)
+            .color(color)
+            .points(points.iter().copied());
+    }
+
+    fn ellipse(&self, center: Vec2, size: Vec2, color: LinSrgba) {
+        nannou::Draw::ellipse(self)
+            .xy(center)
+            .wh(size)
+            .color(color);
+    }
+
+    fn rect(&self, rect: Rect, color: LinSrgba) {
+        nannou::Draw::rect(self)
+            .xy(rect.xy())
+            .wh(rect.wh())
+            .color(color);
+    }
+
+    fn text(&self, text: &str, center: Vec2, width: f32, font_size: u32, color: LinSrgba) {
+        nannou::Draw::text(self, text)
+            .xy(center)
+            .w(width)
+            .color(color)
+            .font_size(font_size);
+    }
+}
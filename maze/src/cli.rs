@@ -0,0 +1,320 @@
+use std::path::PathBuf;
+
+use maze::video::EncoderConfig;
+
+pub const USAGE: &str = "\
+Usage: maze [OPTIONS]
+       maze gif FILE [OPTIONS]
+       maze svg FILE [--animated] [OPTIONS]
+       maze encode FRAMES_FOLDER VIDEO [VIDEO OPTIONS]
+       maze generators
+
+Plays the maze video, records it as a GIF, draws its first maze as an SVG
+picture, encodes the frames saved while exporting it, or lists the
+generators. SVG pictures show the maze solved, or with --animated, being
+generated a step per frame and then solved.
+
+Options:
+  --scenes FILE          Play the scenes of a TOML file instead of the
+                         default video
+  --size WxH             Size of the mazes without a size of their own
+  --generators A,B,...   Only show these generators on a rectangular maze,
+                         e.g. kruskal,binary-tree
+  --start X,Y            First cell of the solution, for --generators
+  --end X,Y              Last cell of the solution, for --generators
+  --seed N               Make the same mazes every time
+  --wait FRAMES          Frames to wait after scenes without a wait of
+                         their own
+  --export FOLDER        Save the video into FOLDER. The window's frames are
+                         only saved with this option
+  --window WxH           Size of the window and of the video
+  --headless             Render the video without showing the window
+  --software             Render the video on the CPU, without a GPU
+  --help                 Show this message
+
+Video options:
+  --fps N                Frame rate of the video
+  --codec NAME           Codec of the video, libx264 by default
+  --pix-fmt NAME         Pixel format of the video, yuv420p by default
+  --encoder PROGRAM      Program taking ffmpeg's options that the frames are
+                         piped into, ffmpeg by default";
+
+/// Options given on the command line. Those left out are taken from the
+/// scenes.
+pub struct Options {
+    pub scene_file: Option<PathBuf>,
+    pub maze_size: Option<(usize, usize)>,
+    pub generators: Option<Vec<String>>,
+    pub start: Option<(usize, usize)>,
+    pub end: Option<(usize, usize)>,
+    pub seed: Option<u64>,
+    pub scene_wait: Option<u32>,
+    /// Frame rate of the video and how it is encoded.
+    pub encoder: EncoderConfig,
+    pub export_folder: Option<PathBuf>,
+    pub window_size: [u32; 2],
+    pub headless: bool,
+    pub software: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            scene_file: None,
+            maze_size: None,
+            generators: None,
+            start: None,
+            end: None,
+            seed: None,
+            scene_wait: None,
+            encoder: EncoderConfig::default(),
+            export_folder: None,
+            window_size: [1280, 720],
+            headless: false,
+            software: false,
+        }
+    }
+}
+
+pub enum Command {
+    Run(Options),
+    Gif {
+        file: PathBuf,
+        options: Options,
+    },
+    Svg {
+        file: PathBuf,
+        animated: bool,
+        options: Options,
+    },
+    Encode {
+        frames_folder: PathBuf,
+        video: PathBuf,
+        encoder: EncoderConfig,
+    },
+    Generators,
+    Help,
+}
+
+/// Reads the command from the arguments, without the program name.
+pub fn parse<I>(args: I) -> Result<Command, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut args = args.into_iter().peekable();
+
+    if args.peek().map(String::as_str) == Some("generators") {
+        args.next();
+        return match args.next() {
+            Some(arg) => Err(format!("Unexpected argument {}", arg)),
+            None => Ok(Command::Generators),
+        };
+    }
+
+    if args.peek().map(String::as_str) == Some("encode") {
+        args.next();
+        let mut positional = Vec::new();
+        let mut encoder = EncoderConfig::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                _ if parse_video_option(&arg, &mut args, &mut encoder)? => {}
+                _ if arg.starts_with("--") => return Err(format!("Unknown option {}", arg)),
+                _ => positional.push(PathBuf::from(arg)),
+            }
+        }
+
+        let [frames_folder, video]: [PathBuf; 2] = positional
+            .try_into()
+            .map_err(|_| "encode takes a frames folder and a video".to_string())?;
+        return Ok(Command::Encode { frames_folder, video, encoder });
+    }
+
+    if let Some(name) = args.next_if(|arg| arg == "gif" || arg == "svg") {
+        let file = args
+            .next_if(|arg| !arg.starts_with("--"))
+            .ok_or_else(|| format!("{} takes a file to save into", name))?
+            .into();
+        let mut animated = false;
+        let rest = args.filter(|arg| {
+            let is_animated = name == "svg" && arg == "--animated";
+            animated |= is_animated;
+            !is_animated
+        });
+
+        return Ok(match parse_options(rest)? {
+            Command::Run(options) if name == "gif" => Command::Gif { file, options },
+            Command::Run(options) => Command::Svg { file, animated, options },
+            help => help,
+        });
+    }
+
+    parse_options(args)
+}
+
+/// Reads the options of the video, or the help option.
+fn parse_options<I>(mut args: I) -> Result<Command, String>
+where
+    I: Iterator<Item = String>,
+{
+    let mut options = Options::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--scenes" => options.scene_file = Some(value(&arg, args.next())?.into()),
+            "--size" => options.maze_size = Some(parse_pair(&arg, args.next(), 'x')?),
+            "--generators" => {
+                let names = value(&arg, args.next())?;
+                options.generators = Some(names.split(',').map(str::to_string).collect());
+            }
+            "--start" => options.start = Some(parse_pair(&arg, args.next(), ',')?),
+            "--end" => options.end = Some(parse_pair(&arg, args.next(), ',')?),
+            "--seed" => options.seed = Some(parse_value(&arg, args.next())?),
+            "--wait" => options.scene_wait = Some(parse_value(&arg, args.next())?),
+            _ if parse_video_option(&arg, &mut args, &mut options.encoder)? => {}
+            "--export" => options.export_folder = Some(value(&arg, args.next())?.into()),
+            "--window" => {
+                let (width, height) = parse_pair(&arg, args.next(), 'x')?;
+                options.window_size = [width, height];
+            }
+            "--headless" => options.headless = true,
+            "--software" => options.software = true,
+            "--help" | "-h" => return Ok(Command::Help),
+            _ => return Err(format!("Unknown option {}", arg)),
+        }
+    }
+
+    let endpoints = options.start.is_some() || options.end.is_some();
+    if endpoints && options.generators.is_none() {
+        return Err("--start and --end need --generators".to_string());
+    }
+    if options.headless && options.software {
+        return Err("--headless and --software can't be used together".to_string());
+    }
+
+    Ok(Command::Run(options))
+}
+
+/// Reads `option` into `encoder` if it is a video option. Returns whether
+/// it was one.
+fn parse_video_option<I>(
+    option: &str,
+    args: &mut I,
+    encoder: &mut EncoderConfig,
+) -> Result<bool, String>
+where
+    I: Iterator<Item = String>,
+{
+    match option {
+        "--fps" => encoder.frame_rate = parse_value(option, args.next())?,
+        "--codec" => encoder.codec = value(option, args.next())?,
+        "--pix-fmt" => encoder.pixel_format = value(option, args.next())?,
+        "--encoder" => encoder.program = value(option, args.next())?,
+        _ => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+fn value(option: &str, value: Option<String>) -> Result<String, String> {
+    value.ok_or_else(|| format!("{} needs a value", option))
+}
+
+fn parse_value<T: std::str::FromStr>(option: &str, value: Option<String>) -> Result<T, String> {
+    let text = self::value(option, value)?;
+    text.parse().map_err(|_| format!("Invalid value {} for {}", text, option))
+}
+
+/// Two numbers separated by `separator`, e.g. `38x20` or `3,4`.
+fn parse_pair<T>(option: &str, value: Option<String>, separator: char) -> Result<(T, T), String>
+where
+    T: std::str::FromStr,
+{
+    let text = self::value(option, value)?;
+    let invalid = || format!("Invalid value {} for {}", text, option);
+
+    let (first, second) = text.split_once(separator).ok_or_else(invalid)?;
+    Ok((first.parse().map_err(|_| invalid())?, second.parse().map_err(|_| invalid())?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &str) -> Result<Command, String> {
+        parse(args.split_whitespace().map(str::to_string))
+    }
+
+    #[test]
+    fn parse_reads_the_options() {
+        let command = parse_args("--size 10x8 --generators kruskal,binary-tree --start 1,2");
+        let options = match command {
+            Ok(Command::Run(options)) => options,
+            _ => panic!("Expected options"),
+        };
+
+        assert_eq!(options.maze_size, Some((10, 8)));
+        assert_eq!(options.generators.unwrap(), vec!["kruskal", "binary-tree"]);
+        assert_eq!(options.start, Some((1, 2)));
+        assert_eq!(options.end, None);
+        assert!(!options.headless && !options.software);
+    }
+
+    #[test]
+    fn parse_rejects_invalid_arguments() {
+        assert!(parse_args("--size 10").is_err());
+        assert!(parse_args("--seed").is_err());
+        assert!(parse_args("--colour blue").is_err());
+        assert!(parse_args("encode frames").is_err());
+        assert!(matches!(parse_args("encode frames video.mp4"), Ok(Command::Encode { .. })));
+        assert!(parse_args("encode frames video.mp4 --codec").is_err());
+        assert!(matches!(parse_args("generators"), Ok(Command::Generators)));
+        assert!(parse_args("generators kruskal").is_err());
+        assert!(parse_args("gif").is_err());
+        assert!(parse_args("gif --size 4x4").is_err());
+        assert!(parse_args("gif maze.gif --animated").is_err());
+        assert!(parse_args("--start 0,0").is_err());
+        assert!(parse_args("--scenes scenes.toml --end 1,1").is_err());
+        assert!(parse_args("--headless --software").is_err());
+    }
+
+    #[test]
+    fn parse_reads_the_video_options() {
+        let options = match parse_args("--fps 30 --codec libx265 --pix-fmt yuv444p") {
+            Ok(Command::Run(options)) => options,
+            _ => panic!("Expected options"),
+        };
+        assert_eq!(options.encoder.frame_rate, 30);
+        assert_eq!(options.encoder.codec, "libx265");
+        assert_eq!(options.encoder.pixel_format, "yuv444p");
+        assert_eq!(options.encoder.program, "ffmpeg");
+
+        match parse_args("encode frames video.webm --codec libvpx-vp9 --encoder avconv") {
+            Ok(Command::Encode { encoder, .. }) => {
+                assert_eq!(encoder.codec, "libvpx-vp9");
+                assert_eq!(encoder.program, "avconv");
+                assert_eq!(encoder.frame_rate, 24);
+            }
+            _ => panic!("Expected encode"),
+        }
+    }
+
+    #[test]
+    fn parse_reads_the_file_and_options_of_pictures() {
+        match parse_args("svg maze.svg --size 6x4 --animated") {
+            Ok(Command::Svg { file, animated, options }) => {
+                assert_eq!(file, PathBuf::from("maze.svg"));
+                assert!(animated);
+                assert_eq!(options.maze_size, Some((6, 4)));
+            }
+            _ => panic!("Expected an SVG"),
+        }
+
+        match parse_args("gif maze.gif --generators kruskal") {
+            Ok(Command::Gif { file, options }) => {
+                assert_eq!(file, PathBuf::from("maze.gif"));
+                assert_eq!(options.generators.unwrap(), vec!["kruskal"]);
+            }
+            _ => panic!("Expected a GIF"),
+        }
+    }
+}
@@ -1,21 +1,22 @@
+use nannou::color::IntoLinSrgba;
 use nannou::prelude::*;
 
-use crate::maze::{Maze, Wall};
-use crate::maze::wall::Direction::*;
+use crate::canvas::Canvas;
+use crate::maze::Topology;
 
-pub struct Draw<'a> {
-    draw: &'a nannou::Draw,
-    maze: &'a Maze,
+pub struct Draw<'a, M: Topology> {
+    draw: &'a dyn Canvas,
+    maze: &'a M,
     color: Rgb8,
     wall_length: f32
 }
 
-impl<'a> Draw<'a> {
+impl<'a, M: Topology> Draw<'a, M> {
     pub fn new(
-        draw: &'a nannou::Draw,
-        maze: &'a Maze,
+        draw: &'a dyn Canvas,
+        maze: &'a M,
         color: Rgb8,
-        wall_length: f32) -> Draw<'a>
+        wall_length: f32) -> Draw<'a, M>
     {
         Draw {
             draw,
@@ -25,36 +26,68 @@ impl<'a> Draw<'a> {
         }
     }
 
-    pub fn wall(&self, wall: &Wall) {
-        if !self.maze.get_wall(wall) {
+    pub fn wall(&self, wall: &M::Wall) {
+        for line in self.maze.wall_lines(wall) {
+            let points: Vec<Vec2> = line
+                .into_iter()
+                .map(|point| point * self.wall_length)
+                .collect();
+
+            self.draw.polyline(&points, 3.0, self.color.into_lin_srgba());
+        }
+
+        if self.maze.get_wall(wall) {
             return;
         }
 
-        let maze_width = self.maze.width() as f32 * self.wall_length;
-        let maze_height = self.maze.height() as f32 * self.wall_length;
-
-        let maze_rect = Rect::from_w_h(maze_width, maze_height);
-
-        let &Wall {x, y, ..} = wall;
-        let cell = Rect::from_w_h(self.wall_length, self.wall_length)
-            .top_left_of(maze_rect)
-            .shift_x(x as f32 * self.wall_length)
-            .shift_y(y as f32 * -self.wall_length);
-
-        let (start, end) = match wall.dir {
-            Up => (cell.top_left(), cell.top_right()),
-            Down => (cell.bottom_left(), cell.bottom_right()),
-            Left => (cell.top_left(), cell.bottom_left()),
-            Right => (cell.top_right(), cell.bottom_right()),
-        };
-
-        self.draw.line()
-            .start(start)
-            .end(end)
-            .color(self.color)
-            .caps_round()
-            .weight(3.0);
+        // Mark both ends of a passage leaving the maze on one edge and
+        // entering it on the other
+        if let Some(wrapped_line) = self.maze.wrapped_wall_line(wall) {
+            for line in [self.maze.wall_line(wall), wrapped_line] {
+                let center = line_middle(&line) * self.wall_length;
+                self.draw.ellipse(center, vec2(8.0, 8.0), self.color.into_lin_srgba());
+            }
+        }
     }
-}
 
+    pub fn cell<C>(&self, cell: M::Cell, color: C)
+    where
+        C: IntoLinSrgba<f32>,
+    {
+        let points: Vec<Vec2> = self.maze
+            .cell_outline(cell)
+            .into_iter()
+            .map(|point| point * self.wall_length)
+            .collect();
+
+        self.draw.polygon(&points, color.into_lin_srgba());
+    }
 
+    /// Fills the smallest rectangle containing both cells.
+    pub fn field<C>(&self, top_left: M::Cell, bottom_right: M::Cell, color: C)
+    where
+        C: IntoLinSrgba<f32>,
+    {
+        let mut corners = self.maze
+            .cell_outline(top_left)
+            .into_iter()
+            .chain(self.maze.cell_outline(bottom_right))
+            .map(|point| point * self.wall_length);
+
+        let first = corners.next().unwrap();
+        let field = corners.fold(Rect::from_corners(first, first), |field, point| {
+            field.stretch_to(point)
+        });
+
+        self.draw.rect(field, color.into_lin_srgba());
+    }
+
+    pub fn cell_center(&self, cell: M::Cell) -> Vec2 {
+        self.maze.cell_center(cell) * self.wall_length
+    }
+}
+
+/// Point halfway between the ends of a line.
+pub fn line_middle(line: &[Vec2]) -> Vec2 {
+    (line[0] + line[line.len() - 1]) / 2.0
+}
@@ -1,57 +1,115 @@
-use nannou::rand::random_range;
+use nannou::rand::rngs::StdRng;
+use nannou::rand::Rng;
 
-use crate::maze::wall::Direction::*;
-use crate::maze::{Maze, Wall};
+use crate::maze::wall::Direction::{self, *};
+use crate::maze::{Maze, Topology, Wall};
 
-use super::MazeGenerator;
+use super::{generator_rng, part_count, DisjointSet, GeneratorState, Highlight, MazeGenerator};
 
+#[derive(Clone)]
 pub struct BinaryTreeGenerator {
     maze: Maze,
+    rng: StdRng,
     cell_index: usize,
+    // Cells joined so far, to join the trees a mask leaves apart at the end
+    sets: DisjointSet,
+    join_index: usize,
 }
 
 impl BinaryTreeGenerator {
     pub fn new(width: usize, height: usize) -> BinaryTreeGenerator {
+        Self::from_maze(Maze::new_with_edges(width, height, true))
+    }
+
+    /// Generator that carves passages into the enabled cells of `maze`,
+    /// which should have all of its walls on. Cells of a mask with no
+    /// enabled cell to their right or below can't be linked onwards, so
+    /// the trees they end are joined to their neighbours afterwards.
+    pub fn from_maze(maze: Maze) -> BinaryTreeGenerator {
+        Self::from_maze_with_rng(maze, generator_rng())
+    }
+
+    /// Generator that links the cells of `maze` the way `rng` picks.
+    pub fn from_maze_with_rng(maze: Maze, rng: StdRng) -> BinaryTreeGenerator {
         BinaryTreeGenerator {
-            maze: Maze::new_with_edges(width, height, true),
+            sets: DisjointSet::new(maze.cell_count()),
+            maze,
+            rng,
             cell_index: 0,
+            join_index: 0,
         }
     }
 
+    fn cell(&self, index: usize) -> (usize, usize) {
+        (index % self.maze.width(), index / self.maze.width())
+    }
+
+    /// Wall to the right or below `cell`, if both of its sides are enabled.
+    fn inner_wall(&self, (x, y): (usize, usize), dir: Direction) -> Option<Wall> {
+        let neighbour = match dir {
+            Right if x + 1 < self.maze.width() => (x + 1, y),
+            Down if y + 1 < self.maze.height() => (x, y + 1),
+            _ => return None,
+        };
+
+        let enabled = self.maze.is_enabled((x, y)) && self.maze.is_enabled(neighbour);
+        enabled.then_some(Wall { x, y, dir })
+    }
+
     fn get_candidate_walls(&self) -> Vec<Wall> {
-        let x = self.cell_index % self.maze.width();
-        let y = self.cell_index / self.maze.width();
-        let mut candidates = Vec::new();
+        let cell = self.cell(self.cell_index);
 
-        let right_wall = Wall { x, y, dir: Right };
-        if x <= self.maze.width() - 2 {
-            candidates.push(right_wall);
-        }
+        [Right, Down]
+            .into_iter()
+            .filter_map(|dir| self.inner_wall(cell, dir))
+            .collect()
+    }
 
-        let down_wall = Wall { x, y, dir: Down };
-        if y <= self.maze.height() - 2 {
-            candidates.push(down_wall)
-        }
+    /// Opens `wall`, joining the cells on both sides of it.
+    fn carve(&mut self, wall: Wall) -> Option<(Wall, bool)> {
+        let across = match wall.dir {
+            Right => (wall.x + 1, wall.y),
+            _ => (wall.x, wall.y + 1),
+        };
+        let cell = self.maze.cell_index((wall.x, wall.y));
+        let across = self.maze.cell_index(across);
 
-        candidates
+        self.sets.union(cell, across).then_some((wall, false))
     }
 }
 
 impl MazeGenerator for BinaryTreeGenerator {
-    fn width(&self) -> usize {
-        self.maze.width()
+    type Maze = Maze;
+
+    fn initial_maze(&self) -> Maze {
+        self.maze.clone()
+    }
+
+    fn name(&self) -> String {
+        "Binary Tree Algorithm".to_string()
     }
 
-    fn height(&self) -> usize {
-        self.maze.height()
+    fn remaining_steps(&self) -> Option<usize> {
+        // Every step joins two trees, until each part is a single one
+        let cells = self.maze.cells().map(|cell| self.maze.cell_index(cell));
+        Some(self.sets.count_sets(cells) - part_count(&self.maze))
     }
 
-    fn initial_maze(&self) -> crate::maze::Maze {
-        Maze::new_with_edges(self.maze.width(), self.maze.height(), true)
+    fn state(&self) -> Option<&dyn GeneratorState<(usize, usize)>> {
+        Some(self)
     }
+}
 
-    fn name(&self) -> String {
-        "Binary Tree Algorithm".to_string()
+impl GeneratorState<(usize, usize)> for BinaryTreeGenerator {
+    fn highlights(&self) -> Vec<Highlight<(usize, usize)>> {
+        if self.cell_index >= self.maze.width() * self.maze.height() {
+            return Vec::new();
+        }
+
+        // The cell whose wall was removed last
+        let cell = self.cell(self.cell_index.saturating_sub(1));
+
+        vec![Highlight::ActiveCell(cell)]
     }
 }
 
@@ -59,21 +117,56 @@ impl Iterator for BinaryTreeGenerator {
     type Item = (Wall, bool);
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.cell_index >= self.maze.width() * self.maze.height() {
-            return None;
+        let cell_count = self.maze.width() * self.maze.height();
+
+        while self.cell_index < cell_count {
+            let mut candidates = self.get_candidate_walls();
+            self.cell_index += 1;
+
+            // Only the last cell has no candidates without a mask
+            let candidate_count = candidates.len();
+            if candidate_count > 0 {
+                let wall_to_remove = candidates.remove(self.rng.gen_range(0..candidate_count));
+                if let Some(step) = self.carve(wall_to_remove) {
+                    return Some(step);
+                }
+            }
         }
 
-        let mut candidates = self.get_candidate_walls();
-        self.cell_index += 1;
+        // Every cell links to a later one, so only masked mazes get here
+        // with separate trees
+        while self.join_index < cell_count * 2 {
+            let cell = self.cell(self.join_index / 2);
+            let dir = [Right, Down][self.join_index % 2];
+            self.join_index += 1;
 
-        let candidate_count = candidates.len();
-        if candidate_count > 0 {
-            let wall_to_remove = candidates.remove(random_range(0, candidate_count));
-            Some((wall_to_remove, false))
-        } else {
-            // This happens only on the last cell
-            None
+            if let Some(step) = self.inner_wall(cell, dir).and_then(|wall| self.carve(wall)) {
+                return Some(step);
+            }
         }
 
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::Mask;
+
+    #[test]
+    fn binary_tree_only_carves_inside_a_mask() {
+        // The top right and bottom left cells both have nothing to link to
+        // on their right or below, so one of them is joined at the end
+        let mask = Mask::from_text("..\n.X\n");
+        let maze = Maze::with_mask(mask, true).unwrap();
+        let mut generator = BinaryTreeGenerator::from_maze(maze.clone());
+        let walls: Vec<Wall> = generator.by_ref().map(|(wall, _)| wall).collect();
+
+        assert_eq!(walls.len(), 3 - 1);
+        assert!(walls.iter().all(|wall| maze.is_enabled((wall.x, wall.y))));
+        assert!(maze.cells().all(|cell| {
+            generator.sets.root(maze.cell_index(cell)) == generator.sets.root(0)
+        }));
     }
 }
@@ -0,0 +1,130 @@
+use std::collections::HashSet;
+
+/// Disjoint-set forest over the elements `0..len`, with path compression
+/// and union by rank.
+#[derive(Clone)]
+pub struct DisjointSet {
+    parents: Vec<usize>,
+    ranks: Vec<u8>,
+}
+
+impl DisjointSet {
+    pub fn new(len: usize) -> DisjointSet {
+        DisjointSet {
+            parents: (0..len).collect(),
+            ranks: vec![0; len],
+        }
+    }
+
+    /// Representative of the set containing `element`, without modifying
+    /// the forest.
+    pub fn root(&self, mut element: usize) -> usize {
+        while self.parents[element] != element {
+            element = self.parents[element];
+        }
+
+        element
+    }
+
+    /// Representative of the set containing `element`. Compresses the path
+    /// so that later lookups are faster.
+    pub fn find(&mut self, element: usize) -> usize {
+        let root = self.root(element);
+
+        let mut element = element;
+        while self.parents[element] != root {
+            let parent = self.parents[element];
+            self.parents[element] = root;
+            element = parent;
+        }
+
+        root
+    }
+
+    /// Number of sets the `elements` are spread over.
+    pub fn count_sets<I: IntoIterator<Item = usize>>(&self, elements: I) -> usize {
+        let roots: HashSet<usize> =
+            elements.into_iter().map(|element| self.root(element)).collect();
+        roots.len()
+    }
+
+    /// Merges the sets containing `a` and `b`. Returns false if they were
+    /// already in the same set.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let a = self.find(a);
+        let b = self.find(b);
+
+        if a == b {
+            return false;
+        }
+
+        let (child, parent) = if self.ranks[a] < self.ranks[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+
+        self.parents[child] = parent;
+        if self.ranks[child] == self.ranks[parent] {
+            self.ranks[parent] += 1;
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disjoint_set_starts_with_every_element_in_its_own_set() {
+        let sets = DisjointSet::new(9);
+
+        for element in 0..9 {
+            assert_eq!(sets.root(element), element);
+        }
+    }
+
+    #[test]
+    fn disjoint_set_union_merges_sets() {
+        let mut sets = DisjointSet::new(5);
+
+        assert!(sets.union(0, 1));
+        assert!(sets.union(3, 1));
+
+        assert_eq!(sets.find(0), sets.find(3));
+        assert_ne!(sets.find(0), sets.find(2));
+    }
+
+    #[test]
+    fn disjoint_set_union_of_same_set_returns_false() {
+        let mut sets = DisjointSet::new(3);
+
+        sets.union(0, 1);
+        sets.union(1, 2);
+
+        assert!(!sets.union(2, 0));
+    }
+
+    #[test]
+    fn disjoint_set_counts_the_sets_of_some_elements() {
+        let mut sets = DisjointSet::new(6);
+        sets.union(0, 1);
+        sets.union(2, 3);
+
+        assert_eq!(sets.count_sets(0..6), 4);
+        assert_eq!(sets.count_sets([0, 1, 3]), 2);
+    }
+
+    #[test]
+    fn disjoint_set_find_compresses_paths() {
+        let mut sets = DisjointSet::new(4);
+        sets.union(0, 1);
+        sets.union(2, 3);
+        sets.union(0, 2);
+
+        let root = sets.find(3);
+        assert!((0..4).all(|element| sets.parents[element] == root));
+    }
+}
@@ -1,119 +1,153 @@
-use std::{
-    cell::RefCell,
-    collections::{HashMap, HashSet}, rc::Rc,
-};
+use nannou::rand::prelude::SliceRandom;
+use nannou::rand::rngs::StdRng;
+use nannou::rand::Rng;
 
-use nannou::rand::prelude::{SliceRandom, ThreadRng};
+use crate::maze::{Axis, Maze, Topology, WeaveMaze, WeaveWall};
 
-use crate::maze::wall::Direction::*;
-use crate::maze::{Maze, Wall};
+use super::{generator_rng, part_count, DisjointSet, MazeGenerator};
 
-use super::MazeGenerator;
-
-pub struct KruskalsGenerator {
-    width: usize,
-    height: usize,
-    sets: HashMap<(usize, usize), Rc<RefCell<HashSet<(usize, usize)>>>>,
-    walls: Vec<Wall>,
+#[derive(Clone)]
+pub struct KruskalsGenerator<M: Topology = Maze> {
+    maze: M,
+    sets: DisjointSet,
+    // Walls between two cells, with the indices of those cells
+    walls: Vec<(M::Wall, usize, usize)>,
 }
 
 impl KruskalsGenerator {
     pub fn new(width: usize, height: usize) -> KruskalsGenerator {
-        let sets = create_sets(width, height);
-        let walls = create_walls(width, height);
-
-        KruskalsGenerator {
-            width,
-            height,
-            sets,
-            walls,
-        }
+        Self::from_maze(Maze::new(width, height, true))
     }
+}
 
-    fn get_cells(&self, wall: &Wall) -> ((usize, usize), (usize, usize)) {
-        match wall {
-            &Wall { x, y, dir: Down } => ((x, y), (x, y + 1)),
-            &Wall { x, y, dir: Right } => ((x, y), (x + 1, y)),
-            _ => panic!("Walls should be generated with only Down and Right dir."),
-        }
+impl KruskalsGenerator<WeaveMaze> {
+    /// Generator for a weave maze with up to `crossings` crossings placed
+    /// at random before the generation starts.
+    pub fn weave(width: usize, height: usize, crossings: usize) -> Self {
+        Self::weave_with_rng(width, height, crossings, generator_rng())
     }
 
-    fn try_remove(&mut self, wall: &Wall) -> bool {
-        let (cell1, cell2) = self.get_cells(wall);
+    pub fn weave_with_rng(width: usize, height: usize, crossings: usize, mut rng: StdRng) -> Self {
+        let mut maze = WeaveMaze::new(width, height, true);
+        let mut sets = DisjointSet::new(maze.cell_count());
 
-        let cell1_set = self.sets.get(&cell1).unwrap();
-        if cell1_set.borrow().contains(&cell2) {
-            return false
-        }
+        let mut cells: Vec<(usize, usize)> = maze.cells().collect();
+        cells.shuffle(&mut rng);
+
+        let mut placed = 0;
+        for (x, y) in cells {
+            if placed == crossings {
+                break;
+            }
+            if !maze.can_cross((x, y)) {
+                continue;
+            }
+
+            // The crossing joins the cells around it, which must not be
+            // connected yet so that no loop is made
+            let over = if rng.gen() { Axis::Vertical } else { Axis::Horizontal };
+            let [crossing, before, after, under_before, under_after] = match over {
+                Axis::Vertical => [(x, y), (x, y - 1), (x, y + 1), (x - 1, y), (x + 1, y)],
+                Axis::Horizontal => [(x, y), (x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)],
+            }
+            .map(|cell| maze.cell_index(cell));
+
+            let roots = [crossing, before, after, under_before, under_after]
+                .map(|index| sets.find(index));
+            if (1..roots.len()).any(|i| roots[..i].contains(&roots[i])) {
+                continue;
+            }
 
-        let cell1_set = self.sets.remove(&cell1).unwrap();
-        let cell2_set = Rc::clone(self.sets.get(&cell2).unwrap());
+            maze.add_crossing((x, y), over);
+            maze.set_wall(&WeaveWall::Tunnel { x, y }, false);
 
-        cell2_set.borrow_mut().extend(cell1_set.borrow().iter());
-        for cell in cell1_set.borrow().iter() {
-            self.sets.insert(*cell, Rc::clone(&cell2_set));
+            sets.union(crossing, before);
+            sets.union(crossing, after);
+            sets.union(under_before, under_after);
+            placed += 1;
         }
 
-        true
+        Self::from_maze_with_rng(maze, rng)
     }
 }
 
-fn create_walls(width: usize, height: usize) -> Vec<Wall> {
-    let mut walls = vec![];
+impl<M: Topology> KruskalsGenerator<M> {
+    /// Generator that carves passages into `maze`. Passages that are
+    /// already open, e.g. over crossings, are kept and never closed.
+    pub fn from_maze(maze: M) -> Self {
+        Self::from_maze_with_rng(maze, generator_rng())
+    }
 
-    for (x, y) in itertools::iproduct!(0..width, 0..height - 1) {
-        walls.push(Wall { x, y, dir: Down });
+    /// Generator that carves passages into `maze` in the order `rng` picks.
+    pub fn from_maze_with_rng(maze: M, mut rng: StdRng) -> Self {
+        let mut sets = DisjointSet::new(maze.cell_count());
+        let mut walls = create_walls(&maze);
+        walls.shuffle(&mut rng);
+
+        for &(wall, cell1, cell2) in &walls {
+            if !maze.get_wall(&wall) {
+                sets.union(cell1, cell2);
+            }
+        }
+
+        KruskalsGenerator { maze, sets, walls }
     }
 
-    for (x, y) in itertools::iproduct!(0..width - 1, 0..height) {
-        walls.push(Wall { x, y, dir: Right });
+    /// Identifier of the set `cell` currently belongs to. Two cells are
+    /// connected by the carved passages iff they have the same set id.
+    pub fn set_id(&self, cell: M::Cell) -> usize {
+        self.sets.root(self.maze.cell_index(cell))
     }
+}
 
-    walls.shuffle(&mut ThreadRng::default());
+fn create_walls<M: Topology>(maze: &M) -> Vec<(M::Wall, usize, usize)> {
+    let mut walls = vec![];
+
+    for cell in maze.cells() {
+        let index = maze.cell_index(cell);
+
+        // Every wall is seen from both of its cells, keep it only once
+        walls.extend(
+            maze.neighbours(cell)
+                .into_iter()
+                .map(|(neighbour, wall)| (wall, index, maze.cell_index(neighbour)))
+                .filter(|&(_, index, neighbour_index)| index < neighbour_index),
+        );
+    }
 
     walls
 }
 
-fn create_sets(
-    width: usize,
-    height: usize,
-) -> HashMap<(usize, usize), Rc<RefCell<HashSet<(usize, usize)>>>> {
-    itertools::iproduct!(0..width, 0..height)
-        .map(|position| {
-            let set = Rc::new(RefCell::new(HashSet::new()));
-            set.borrow_mut().insert(position);
-
-            (position, set)
-        })
-        .collect()
-}
+impl<M: Topology> MazeGenerator for KruskalsGenerator<M> {
+    type Maze = M;
 
-impl MazeGenerator for KruskalsGenerator {
-    fn width(&self) -> usize {
-        self.width
+    fn initial_maze(&self) -> M {
+        self.maze.clone()
     }
 
-    fn height(&self) -> usize {
-        self.height
+    fn name(&self) -> String {
+        "Kruskal's Algorithm".to_string()
     }
 
-    fn initial_maze(&self) -> crate::maze::Maze {
-        Maze::new_with_edges(self.width, self.height, true)
+    fn remaining_steps(&self) -> Option<usize> {
+        // Every step joins two regions, until each part is a single one
+        let cells = self.maze.cells().map(|cell| self.maze.cell_index(cell));
+        Some(self.sets.count_sets(cells) - part_count(&self.maze))
     }
 
-    fn name(&self) -> String {
-        "Kruskal's Algorithm".to_string()
+    fn region(&self, cell: M::Cell) -> Option<usize> {
+        Some(self.set_id(cell))
     }
 }
 
-impl Iterator for KruskalsGenerator {
-    type Item = (Wall, bool);
+impl<M: Topology> Iterator for KruskalsGenerator<M> {
+    type Item = (M::Wall, bool);
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            let wall = self.walls.pop()?;
+            let (wall, cell1, cell2) = self.walls.pop()?;
 
-            if self.try_remove(&wall) {
+            if self.sets.union(cell1, cell2) {
                 return Some((wall, false));
             }
         }
@@ -122,26 +156,73 @@ impl Iterator for KruskalsGenerator {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
+
+    use crate::maze::{HexMaze, Mask};
+
     use super::*;
 
     #[test]
     fn create_walls_should_return_appropriate_number_of_walls() {
-        let walls = create_walls(3, 3);
+        let walls = create_walls(&Maze::new(3, 3, true));
         assert_eq!(walls.len(), 12);
     }
 
     #[test]
-    fn create_sets_should_return_appropriate_number_of_sets() {
-        let sets = create_sets(3, 3);
-        assert_eq!(sets.len(), 9);
+    fn set_ids_should_be_distinct_before_generation() {
+        let generator = KruskalsGenerator::new(3, 3);
+
+        let ids: HashSet<usize> = itertools::iproduct!(0..3, 0..3)
+            .map(|cell| generator.set_id(cell))
+            .collect();
+
+        assert_eq!(ids.len(), 9);
     }
 
     #[test]
-    fn create_sets_should_return_sets_with_size_one() {
-        let sets = create_sets(3, 3);
+    fn set_ids_should_be_equal_after_generation() {
+        let mut generator = KruskalsGenerator::new(4, 3);
+        let removed = generator.by_ref().count();
 
-        for (_, set) in sets.into_iter() {
-            assert_eq!(set.borrow().len(), 1);
-        }
+        assert_eq!(removed, 4 * 3 - 1);
+        assert!(itertools::iproduct!(0..4, 0..3)
+            .all(|cell| generator.set_id(cell) == generator.set_id((0, 0))));
+    }
+
+    #[test]
+    fn generator_should_connect_all_cells_of_hex_maze() {
+        let maze = HexMaze::new(5, 4, true);
+        let mut generator = KruskalsGenerator::from_maze(maze);
+        let removed = generator.by_ref().count();
+
+        assert_eq!(removed, 5 * 4 - 1);
+        assert!(itertools::iproduct!(0..5, 0..4)
+            .all(|cell| generator.set_id(cell) == generator.set_id((0, 0))));
+    }
+
+    #[test]
+    fn generator_should_only_carve_inside_mask() {
+        let mask = Mask::from_text("X..X\n....\nX..X\n");
+        let mut generator = KruskalsGenerator::from_maze(Maze::with_mask(mask, true).unwrap());
+        let removed = generator.by_ref().count();
+
+        assert_eq!(removed, 8 - 1);
+    }
+
+    #[test]
+    fn weave_generator_should_connect_all_cells_without_loops() {
+        let mut generator = KruskalsGenerator::weave(8, 8, 10);
+        let maze = generator.initial_maze();
+        let carved = generator.by_ref().count();
+
+        let crossings: Vec<_> = maze.cells().filter(|&cell| maze.crossing(cell).is_some()).collect();
+        let open_walls = maze.walls().filter(|wall| !maze.get_wall(wall)).count();
+
+        // A spanning tree has one passage less than cells, and crossings
+        // come with three passages already open
+        assert!(!crossings.is_empty());
+        assert_eq!(open_walls, crossings.len() * 3);
+        assert_eq!(carved + open_walls, 8 * 8 - 1);
+        assert!(maze.cells().all(|cell| generator.set_id(cell) == generator.set_id((0, 0))));
     }
 }
@@ -1,4 +1,4 @@
-use crate::maze::{Wall, Maze};
+use crate::maze::Topology;
 
 mod binary_tree;
 pub use binary_tree::*;
@@ -9,12 +9,82 @@ pub use recursive_division::*;
 mod kruskal;
 pub use kruskal::*;
 
+mod disjoint_set;
+pub use disjoint_set::*;
+
+mod state;
+pub use state::*;
+
 mod recursive_backtracking;
 pub use recursive_backtracking::*;
 
-pub trait MazeGenerator: Iterator<Item = (Wall, bool)> {
-    fn width(&self) -> usize;
-    fn height(&self) -> usize;
-    fn initial_maze(&self) -> Maze;
+mod rng;
+pub use rng::*;
+
+mod registry;
+pub use registry::*;
+
+/// Number of separate parts of `maze` once all of its walls are open, e.g.
+/// the letters of a title. Generators make each part a maze of its own.
+pub(crate) fn part_count<M: Topology>(maze: &M) -> usize {
+    let mut sets = DisjointSet::new(maze.cell_count());
+    for cell in maze.cells() {
+        for (neighbour, _) in maze.neighbours(cell) {
+            sets.union(maze.cell_index(cell), maze.cell_index(neighbour));
+        }
+    }
+
+    sets.count_sets(maze.cells().map(|cell| maze.cell_index(cell)))
+}
+
+/// Produces a maze step by step, as a sequence of walls to turn on or off
+/// in `initial_maze`.
+pub trait MazeGenerator: Iterator<Item = (<Self::Maze as Topology>::Wall, bool)> {
+    type Maze: Topology;
+
+    fn initial_maze(&self) -> Self::Maze;
     fn name(&self) -> String;
-}
\ No newline at end of file
+
+    /// Number of steps left before the maze is done, for pacing the
+    /// animation, or `None` if the generator can't tell without running.
+    fn remaining_steps(&self) -> Option<usize> {
+        None
+    }
+
+    /// Label of the region `cell` currently belongs to, for generators that
+    /// grow the maze from separate regions. Cells with the same label are
+    /// connected.
+    fn region(&self, _cell: <Self::Maze as Topology>::Cell) -> Option<usize> {
+        None
+    }
+
+    /// Internal state of the generator, for generators that can describe
+    /// what they are currently doing.
+    fn state(&self) -> Option<&dyn GeneratorState<<Self::Maze as Topology>::Cell>> {
+        None
+    }
+}
+
+impl<G: MazeGenerator + ?Sized> MazeGenerator for Box<G> {
+    type Maze = G::Maze;
+
+    fn initial_maze(&self) -> Self::Maze {
+        (**self).initial_maze()
+    }
+
+    fn name(&self) -> String {
+        (**self).name()
+    }
+
+    fn remaining_steps(&self) -> Option<usize> {
+        (**self).remaining_steps()
+    }
+
+    fn region(&self, cell: <Self::Maze as Topology>::Cell) -> Option<usize> {
+        (**self).region(cell)
+    }
+
+    fn state(&self) -> Option<&dyn GeneratorState<<Self::Maze as Topology>::Cell>> {
+        (**self).state()
+    }
+}
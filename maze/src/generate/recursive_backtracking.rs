@@ -1,47 +1,67 @@
 use std::collections::HashSet;
 
 use nannou::rand::prelude::IteratorRandom;
-use nannou::rand::{random_range, thread_rng};
+use nannou::rand::rngs::StdRng;
 
-use crate::maze::wall::Direction;
-use crate::maze::wall::Direction::*;
-use crate::maze::{Maze, Wall};
+use crate::maze::{Maze, Topology};
 
-use super::MazeGenerator;
+use super::{generator_rng, part_count, GeneratorState, Highlight, MazeGenerator};
 
-pub struct RecursiveBacktrackingGenerator {
-    width: usize,
-    height: usize,
-    visited_cells: HashSet<(usize, usize)>,
-    backtrack_stack: Vec<(usize, usize)>,
-    current_cell: (usize, usize),
+#[derive(Clone)]
+pub struct RecursiveBacktrackingGenerator<M: Topology = Maze> {
+    maze: M,
+    rng: StdRng,
+    visited_cells: HashSet<M::Cell>,
+    backtrack_stack: Vec<M::Cell>,
+    current_cell: M::Cell,
+    // Parts of the maze, e.g. letters of a title, not started yet
+    parts_left: usize,
 }
 
 impl RecursiveBacktrackingGenerator {
     pub fn new(width: usize, height: usize) -> Self {
-        let current_cell = (random_range(0, width), random_range(0, height));
+        Self::from_maze(Maze::new(width, height, true))
+    }
+}
+
+impl<M: Topology> RecursiveBacktrackingGenerator<M> {
+    /// Generator that carves passages into `maze`, which should have all
+    /// of its walls on.
+    pub fn from_maze(maze: M) -> Self {
+        Self::from_maze_with_rng(maze, generator_rng())
+    }
+
+    /// Generator that carves passages into `maze` in the order `rng` picks.
+    pub fn from_maze_with_rng(maze: M, mut rng: StdRng) -> Self {
+        let current_cell = maze
+            .cells()
+            .choose(&mut rng)
+            .expect("Maze should have at least one cell");
 
         let mut visited_cells = HashSet::new();
         visited_cells.insert(current_cell);
 
         Self {
-            width,
-            height,
+            parts_left: part_count(&maze) - 1,
+            maze,
+            rng,
             visited_cells,
             backtrack_stack: vec![current_cell],
             current_cell,
         }
     }
 
-    fn next_unvisited(&self) -> Option<((usize, usize), Wall)> {
-        [Up, Down, Left, Right]
+    fn next_unvisited(&mut self) -> Option<(M::Cell, M::Wall)> {
+        let visited_cells = &self.visited_cells;
+
+        self.maze
+            .neighbours(self.current_cell)
             .into_iter()
-            .filter_map(|dir| self.get_neighbor(self.current_cell, dir))
-            .filter(|(cell, _)| !self.visited_cells.contains(cell))
-            .choose(&mut thread_rng())
+            .filter(|(cell, _)| !visited_cells.contains(cell))
+            .choose(&mut self.rng)
     }
 
-    fn backtrack_and_next_unvisited(&mut self) -> Option<((usize, usize), Wall)> {
+    fn backtrack_and_next_unvisited(&mut self) -> Option<(M::Cell, M::Wall)> {
         loop {
             self.backtrack_stack.pop();
             self.current_cell = *self.backtrack_stack.last()?;
@@ -52,49 +72,68 @@ impl RecursiveBacktrackingGenerator {
         }
     }
 
-    fn get_neighbor(&self, cell: (usize, usize), dir: Direction) -> Option<((usize, usize), Wall)> {
-        let (x, y) = cell;
-
-        let neighbor = match dir {
-            Up if y == 0 => None,
-            Up => Some((x, y - 1)),
-            Down if y >= self.height - 1 => None,
-            Down => Some((x, y + 1)),
-            Left if x == 0 => None,
-            Left => Some((x - 1, y)),
-            Right if x >= self.width - 1 => None,
-            Right => Some((x + 1, y)),
-        }?;
-
-        Some((neighbor, Wall { x, y, dir }))
+    /// Continues from an unvisited cell, for mazes made of separate parts.
+    fn restart(&mut self) -> Option<()> {
+        let cell = self.maze.cells().find(|cell| !self.visited_cells.contains(cell))?;
+
+        self.current_cell = cell;
+        self.visited_cells.insert(cell);
+        self.backtrack_stack = vec![cell];
+        self.parts_left -= 1;
+
+        Some(())
     }
 }
 
-impl MazeGenerator for RecursiveBacktrackingGenerator {
-    fn width(&self) -> usize {
-        self.width
+impl<M: Topology> MazeGenerator for RecursiveBacktrackingGenerator<M> {
+    type Maze = M;
+
+    fn initial_maze(&self) -> M {
+        self.maze.clone()
     }
 
-    fn height(&self) -> usize {
-        self.height
+    fn name(&self) -> String {
+        "Recursive Backtracking".to_string()
     }
 
-    fn initial_maze(&self) -> crate::maze::Maze {
-        Maze::new(self.width, self.height, true)
+    fn remaining_steps(&self) -> Option<usize> {
+        // Every step visits a new cell, except for the first cell of a part
+        let unvisited = self.maze.cells().count() - self.visited_cells.len();
+        Some(unvisited - self.parts_left)
     }
 
-    fn name(&self) -> String {
-        "Recursive Backtracking".to_string()
+    fn state(&self) -> Option<&dyn GeneratorState<M::Cell>> {
+        Some(self)
     }
 }
 
-impl Iterator for RecursiveBacktrackingGenerator {
-    type Item = (Wall, bool);
+impl<M: Topology> GeneratorState<M::Cell> for RecursiveBacktrackingGenerator<M> {
+    fn highlights(&self) -> Vec<Highlight<M::Cell>> {
+        if self.backtrack_stack.is_empty() {
+            return Vec::new();
+        }
+
+        vec![
+            Highlight::Stack(self.backtrack_stack.clone()),
+            Highlight::ActiveCell(self.current_cell),
+        ]
+    }
+}
+
+impl<M: Topology> Iterator for RecursiveBacktrackingGenerator<M> {
+    type Item = (M::Wall, bool);
 
     fn next(&mut self) -> Option<Self::Item> {
-        let (next_cell, carved_wall) = self
-            .next_unvisited()
-            .or_else(|| self.backtrack_and_next_unvisited())?;
+        let (next_cell, carved_wall) = loop {
+            if let Some(next) = self
+                .next_unvisited()
+                .or_else(|| self.backtrack_and_next_unvisited())
+            {
+                break next;
+            }
+
+            self.restart()?;
+        };
 
         self.current_cell = next_cell;
         self.visited_cells.insert(next_cell);
@@ -103,3 +142,20 @@ impl Iterator for RecursiveBacktrackingGenerator {
         Some((carved_wall, false))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::Mask;
+
+    #[test]
+    fn recursive_backtracking_carves_every_part_of_a_mask() {
+        // Two columns of cells separated by a disabled one
+        let mask = Mask::from_text(".X.\n.X.\n.X.\n");
+        let maze = Maze::with_mask(mask, true).unwrap();
+        let generator = RecursiveBacktrackingGenerator::from_maze(maze);
+
+        // One carved wall per cell, except for the first cell of each part
+        assert_eq!(generator.count(), 4);
+    }
+}
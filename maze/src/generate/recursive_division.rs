@@ -1,11 +1,12 @@
-use nannou::rand::random_range;
+use nannou::rand::rngs::StdRng;
+use nannou::rand::Rng;
 
 use crate::maze::wall::Direction::*;
 use crate::maze::{Maze, Wall};
 
-use super::MazeGenerator;
+use super::{generator_rng, GeneratorState, Highlight, MazeGenerator};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 struct Field {
     x: usize,
     y: usize,
@@ -14,8 +15,8 @@ struct Field {
 }
 
 impl Field {
-    fn split_vertically(self) -> (Field, Field) {
-        let left_width = random_range(1, self.width);
+    fn split_vertically<R: Rng>(self, rng: &mut R) -> (Field, Field) {
+        let left_width = rng.gen_range(1..self.width);
         let left = Field {
             x: self.x,
             y: self.y,
@@ -32,8 +33,8 @@ impl Field {
         (left, right)
     }
 
-    fn split_horizontally(self) -> (Field, Field) {
-        let top_height = random_range(1, self.height);
+    fn split_horizontally<R: Rng>(self, rng: &mut R) -> (Field, Field) {
+        let top_height = rng.gen_range(1..self.height);
         let top = Field {
             x: self.x,
             y: self.y,
@@ -55,58 +56,102 @@ impl Field {
     }
 }
 
+#[derive(Clone)]
 pub struct RecursiveDivisionGenerator {
-    width: usize,
-    height: usize,
+    maze: Maze,
+    rng: StdRng,
     pending_stack: Vec<(Wall, bool)>,
     field_stack: Vec<Field>,
+    // Field whose split walls are being laid and the cell next to their gap
+    current_field: Option<Field>,
+    current_gap: Option<(usize, usize)>,
+    steps_left: usize,
 }
 
 impl RecursiveDivisionGenerator {
     pub fn new(width: usize, height: usize) -> Self {
+        Self::from_maze(Maze::new_with_edges(width, height, false))
+    }
+
+    /// Generator that divides the enabled cells of `maze`, which should
+    /// have its inner walls off. Split walls are only laid between enabled
+    /// cells, with a gap in each stretch of them.
+    pub fn from_maze(maze: Maze) -> Self {
+        Self::from_maze_with_rng(maze, generator_rng())
+    }
+
+    /// Generator that divides `maze` where `rng` picks.
+    pub fn from_maze_with_rng(maze: Maze, rng: StdRng) -> Self {
         let field_stack = vec![Field {
             x: 0,
             y: 0,
-            width,
-            height,
+            width: maze.width(),
+            height: maze.height(),
         }];
 
-        Self {
-            width,
-            height,
+        let mut generator = Self {
+            maze,
+            rng,
             pending_stack: Vec::new(),
             field_stack,
+            current_field: None,
+            current_gap: None,
+            steps_left: 0,
+        };
+        // Split lines and the stretches left open on them are picked at
+        // random, so the steps can only be counted by taking them
+        generator.steps_left = generator.clone().count();
+        generator
+    }
+
+    /// Whether both sides of `wall`, on the right or below its cell, are
+    /// enabled.
+    fn is_inside(&self, wall: &Wall) -> bool {
+        let across = match wall.dir {
+            Right => (wall.x + 1, wall.y),
+            _ => (wall.x, wall.y + 1),
+        };
+
+        self.maze.is_enabled((wall.x, wall.y)) && self.maze.is_enabled(across)
+    }
+
+    /// Lays the walls of a split line, given in order, with a gap in each
+    /// stretch of walls between enabled cells. A stretch of a single wall
+    /// is left open as its own gap.
+    fn lay_line<I: Iterator<Item = Wall>>(&mut self, line: I) {
+        let mut stretches: Vec<Vec<Wall>> = vec![Vec::new()];
+        for wall in line {
+            if self.is_inside(&wall) {
+                stretches.last_mut().unwrap().push(wall);
+            } else if !stretches.last().unwrap().is_empty() {
+                stretches.push(Vec::new());
+            }
         }
+        stretches.retain(|stretch| stretch.len() > 1);
+
+        let gaps: Vec<Wall> = stretches
+            .iter()
+            .map(|stretch| stretch[self.rng.gen_range(0..stretch.len())])
+            .collect();
+        self.current_gap = gaps.first().map(|gap| (gap.x, gap.y));
+
+        // Since this is a stack, push the wall gaps first
+        self.pending_stack.extend(gaps.into_iter().rev().map(|gap| (gap, false)));
+
+        // Add the split walls
+        let walls = stretches.into_iter().flatten().rev().map(|wall| (wall, true));
+        self.pending_stack.extend(walls);
     }
 
     fn split_vertically(&mut self, field: Field) {
-        let (left, right) = field.split_vertically();
+        let (left, right) = field.split_vertically(&mut self.rng);
         let split_x = left.x + left.width - 1;
 
-        if left.height > 1 {
-            let gap_y = random_range(left.y, left.y + left.height);
-            let gap_wall = Wall {
-                x: split_x,
-                y: gap_y,
-                dir: Right,
-            };
-
-            // Since this is a stack, push the wall gap first
-            self.pending_stack.push((gap_wall, false));
-
-            // Add the split walls
-            self.pending_stack
-                .extend((left.y..(left.y + left.height)).rev().map(|y| {
-                    (
-                        Wall {
-                            x: split_x,
-                            y,
-                            dir: Right,
-                        },
-                        true,
-                    )
-                }));
-        }
+        self.lay_line((left.y..(left.y + left.height)).map(|y| Wall {
+            x: split_x,
+            y,
+            dir: Right,
+        }));
 
         // Add the split fields to stack
         if left.is_splittable() {
@@ -118,33 +163,14 @@ impl RecursiveDivisionGenerator {
     }
 
     fn split_horizontally(&mut self, field: Field) {
-        let (top, bottom) = field.split_horizontally();
+        let (top, bottom) = field.split_horizontally(&mut self.rng);
         let split_y = top.y + top.height - 1;
 
-        if top.width > 1 {
-            let gap_x = random_range(top.x, top.x + top.width);
-            let gap_wall = Wall {
-                x: gap_x,
-                y: split_y,
-                dir: Down,
-            };
-
-            // Since this is a stack, push the wall gap first
-            self.pending_stack.push((gap_wall, false));
-
-            // Add the split walls
-            self.pending_stack
-                .extend((top.x..(top.x + top.width)).rev().map(|x| {
-                    (
-                        Wall {
-                            x,
-                            y: split_y,
-                            dir: Down,
-                        },
-                        true,
-                    )
-                }));
-        }
+        self.lay_line((top.x..(top.x + top.width)).map(|x| Wall {
+            x,
+            y: split_y,
+            dir: Down,
+        }));
 
         // Add the split fields to stack
         if top.is_splittable() {
@@ -157,20 +183,42 @@ impl RecursiveDivisionGenerator {
 }
 
 impl MazeGenerator for RecursiveDivisionGenerator {
-    fn width(&self) -> usize {
-        self.width
+    type Maze = Maze;
+
+    fn initial_maze(&self) -> Maze {
+        self.maze.clone()
     }
 
-    fn height(&self) -> usize {
-        self.width
+    fn name(&self) -> String {
+        "Recursive Division Algorithm".to_string()
     }
 
-    fn initial_maze(&self) -> crate::maze::Maze {
-        Maze::new_with_edges(self.width, self.height, false)
+    fn remaining_steps(&self) -> Option<usize> {
+        Some(self.steps_left)
     }
 
-    fn name(&self) -> String {
-        "Recursive Division Algorithm".to_string()
+    fn state(&self) -> Option<&dyn GeneratorState<(usize, usize)>> {
+        Some(self)
+    }
+}
+
+impl GeneratorState<(usize, usize)> for RecursiveDivisionGenerator {
+    fn highlights(&self) -> Vec<Highlight<(usize, usize)>> {
+        let mut highlights = Vec::new();
+
+        // Before the first split, show the field that will be split next
+        if let Some(field) = self.current_field.or_else(|| self.field_stack.last().copied()) {
+            highlights.push(Highlight::Field {
+                top_left: (field.x, field.y),
+                bottom_right: (field.x + field.width - 1, field.y + field.height - 1),
+            });
+        }
+
+        if let Some(cell) = self.current_gap {
+            highlights.push(Highlight::Gap(cell));
+        }
+
+        highlights
     }
 }
 
@@ -179,7 +227,14 @@ impl Iterator for RecursiveDivisionGenerator {
 
     fn next(&mut self) -> Option<Self::Item> {
         while self.pending_stack.is_empty() {
-            let field = self.field_stack.pop()?;
+            let Some(field) = self.field_stack.pop() else {
+                self.current_field = None;
+                self.current_gap = None;
+                return None;
+            };
+
+            self.current_field = Some(field);
+            self.current_gap = None;
 
             if field.width > field.height {
                 self.split_vertically(field)
@@ -188,6 +243,7 @@ impl Iterator for RecursiveDivisionGenerator {
             };
         }
 
+        self.steps_left = self.steps_left.saturating_sub(1);
         self.pending_stack.pop()
     }
 }
@@ -195,6 +251,8 @@ impl Iterator for RecursiveDivisionGenerator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::maze::{Mask, Topology};
+    use crate::solve::solve;
 
     #[test]
     fn field_vertical_split_should_split_the_width() {
@@ -204,7 +262,7 @@ mod tests {
             width: 5,
             height: 5,
         };
-        let (left, right) = field.split_vertically();
+        let (left, right) = field.split_vertically(&mut generator_rng());
 
         assert_eq!(left.width + right.width, 5);
     }
@@ -217,7 +275,7 @@ mod tests {
             width: 2,
             height: 5,
         };
-        let (left, right) = field.split_vertically();
+        let (left, right) = field.split_vertically(&mut generator_rng());
 
         assert_eq!(left.width, 1);
         assert_eq!(left.x, 0);
@@ -234,7 +292,7 @@ mod tests {
             width: 5,
             height: 5,
         };
-        let (top, bottom) = field.split_horizontally();
+        let (top, bottom) = field.split_horizontally(&mut generator_rng());
 
         assert_eq!(top.height + bottom.height, 5);
     }
@@ -247,7 +305,7 @@ mod tests {
             width: 5,
             height: 2,
         };
-        let (top, bottom) = field.split_horizontally();
+        let (top, bottom) = field.split_horizontally(&mut generator_rng());
 
         assert_eq!(top.height, 1);
         assert_eq!(top.y, 0);
@@ -255,4 +313,90 @@ mod tests {
         assert_eq!(bottom.height, 1);
         assert_eq!(bottom.y, 1);
     }
+
+    #[test]
+    fn generator_should_keep_the_height_of_non_square_mazes() {
+        let mut generator = RecursiveDivisionGenerator::new(7, 3);
+        let maze = generator.initial_maze();
+
+        assert_eq!((maze.width(), maze.height()), (7, 3));
+        assert!(generator.all(|(wall, _)| wall.x < 7 && wall.y < 3));
+    }
+
+    #[test]
+    fn generator_should_divide_all_of_a_wide_maze_into_a_perfect_maze() {
+        let generator = RecursiveDivisionGenerator::new(7, 3);
+        let mut generated = generator.initial_maze();
+        for (wall, on) in generator {
+            generated.set_wall(&wall, on);
+        }
+
+        // Every cell is reached by exactly one path, so the passages
+        // between them make a tree
+        let passages = generated.walls().filter(|wall| !generated.get_wall(wall)).count();
+        assert_eq!(passages, generated.cell_count() - 1);
+        for cell in generated.cells() {
+            assert!(solve(&generated, (0, 0), cell).is_some());
+        }
+    }
+
+    #[test]
+    fn generator_should_only_lay_walls_inside_a_mask() {
+        let mask = Mask::from_text("X....\n.....\n....X\n");
+        let maze = Maze::with_mask(mask, false).unwrap();
+        let generator = RecursiveDivisionGenerator::from_maze(maze.clone());
+
+        let mut generated = maze.clone();
+        for (wall, on) in generator {
+            assert!(maze.is_enabled((wall.x, wall.y)));
+            generated.set_wall(&wall, on);
+        }
+
+        // Every enabled cell can still be reached
+        for cell in maze.cells().filter(|&cell| cell != (1, 0)) {
+            assert_eq!(solve(&generated, (1, 0), cell).unwrap().last(), Some(&cell));
+        }
+    }
+
+    #[test]
+    fn highlights_should_contain_the_whole_maze_before_generation() {
+        let generator = RecursiveDivisionGenerator::new(6, 4);
+
+        match generator.highlights().as_slice() {
+            [Highlight::Field { top_left: (0, 0), bottom_right: (5, 3) }] => (),
+            _ => panic!("Expected a single field covering the maze"),
+        }
+    }
+
+    #[test]
+    fn highlights_should_contain_the_gap_inside_the_split_field() {
+        let mut generator = RecursiveDivisionGenerator::new(6, 4);
+        generator.next();
+
+        match generator.highlights().as_slice() {
+            [Highlight::Field { top_left, bottom_right }, Highlight::Gap((gap_x, gap_y))] => {
+                assert!((top_left.0..=bottom_right.0).contains(gap_x));
+                assert!((top_left.1..=bottom_right.1).contains(gap_y));
+            }
+            _ => panic!("Expected the split field and its gap"),
+        }
+    }
+
+    #[test]
+    fn highlights_should_be_empty_after_generation() {
+        let mut generator = RecursiveDivisionGenerator::new(6, 4);
+        generator.by_ref().count();
+
+        assert!(generator.highlights().is_empty());
+    }
+
+    #[test]
+    fn remaining_steps_counts_the_steps_left() {
+        let mut generator = RecursiveDivisionGenerator::new(6, 4);
+        generator.by_ref().take(5).count();
+
+        let remaining = generator.remaining_steps().unwrap();
+
+        assert_eq!(generator.count(), remaining);
+    }
 }
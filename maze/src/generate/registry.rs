@@ -0,0 +1,400 @@
+use std::fmt;
+
+use nannou::rand::rngs::StdRng;
+use nannou::rand::SeedableRng;
+use serde::Deserialize;
+
+use crate::maze::{DeltaMaze, HexMaze, LevelMaze, Mask, Maze, PolarMaze, Topology, WeaveMaze, Wrap};
+
+use super::{
+    generator_rng, BinaryTreeGenerator, KruskalsGenerator, MazeGenerator,
+    RecursiveBacktrackingGenerator, RecursiveDivisionGenerator,
+};
+
+pub type BoxedGenerator<M> =
+    Box<dyn MazeGenerator<Maze = M, Item = (<M as Topology>::Wall, bool)>>;
+
+/// Kind of maze to generate.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Shape {
+    #[default]
+    Rect,
+    Hex,
+    Polar,
+    Delta,
+    Cylinder,
+    Torus,
+    Weave,
+    Levels,
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Shape::Rect => "rect",
+            Shape::Hex => "hex",
+            Shape::Polar => "polar",
+            Shape::Delta => "delta",
+            Shape::Cylinder => "cylinder",
+            Shape::Torus => "torus",
+            Shape::Weave => "weave",
+            Shape::Levels => "levels",
+        };
+        f.write_str(name)
+    }
+}
+
+/// What a generator from the registry is made with.
+#[derive(Clone)]
+pub struct GeneratorOptions {
+    pub size: [usize; 2],
+    /// Seed of the generator, or the next one from `seed_generators`. It
+    /// only seeds this generator, not the ones made after it.
+    pub seed: Option<u64>,
+    pub shape: Shape,
+    /// Rings of a polar maze, half the height by default.
+    pub rings: Option<usize>,
+    /// Levels of a levels maze, 3 by default.
+    pub levels: Option<usize>,
+    /// Crossings of a weave maze, one for every 6 cells by default.
+    pub crossings: Option<usize>,
+    /// Shape of a rect maze, instead of its size.
+    pub mask: Option<Mask>,
+}
+
+impl GeneratorOptions {
+    pub fn new(width: usize, height: usize) -> Self {
+        GeneratorOptions {
+            size: [width, height],
+            seed: None,
+            shape: Shape::Rect,
+            rings: None,
+            levels: None,
+            crossings: None,
+            mask: None,
+        }
+    }
+
+    fn rng(&self) -> StdRng {
+        match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => generator_rng(),
+        }
+    }
+
+    fn rect_maze(&self) -> Result<Maze, String> {
+        let [width, height] = self.size;
+
+        match (&self.mask, self.shape) {
+            (Some(mask), _) => Maze::with_mask(mask.clone(), true),
+            (None, Shape::Cylinder) => Maze::with_wrap(width, height, Wrap::Cylinder, true),
+            (None, Shape::Torus) => Maze::with_wrap(width, height, Wrap::Torus, true),
+            (None, _) => Ok(Maze::new(width, height, true)),
+        }
+    }
+}
+
+/// Generator made from the registry, by the kind of maze it generates.
+/// Cylinder, torus and masked mazes are `Rect` ones.
+pub enum AnyGenerator {
+    Rect(BoxedGenerator<Maze>),
+    Hex(BoxedGenerator<HexMaze>),
+    Polar(BoxedGenerator<PolarMaze>),
+    Delta(BoxedGenerator<DeltaMaze>),
+    Weave(BoxedGenerator<WeaveMaze>),
+    Levels(BoxedGenerator<LevelMaze>),
+}
+
+pub struct GeneratorEntry {
+    /// Stable name of the generator on the command line and in scene files.
+    pub key: &'static str,
+    /// Name the generator shows, as returned by `MazeGenerator::name`.
+    pub name: &'static str,
+    pub description: &'static str,
+    pub shapes: &'static [Shape],
+    factory: fn(&GeneratorOptions) -> Result<AnyGenerator, String>,
+}
+
+const ALL_SHAPES: &[Shape] = &[
+    Shape::Rect,
+    Shape::Hex,
+    Shape::Polar,
+    Shape::Delta,
+    Shape::Cylinder,
+    Shape::Torus,
+    Shape::Weave,
+    Shape::Levels,
+];
+
+/// Every generator, in the order they are listed to users.
+pub const GENERATORS: &[GeneratorEntry] = &[
+    GeneratorEntry {
+        key: "kruskal",
+        name: "Kruskal's Algorithm",
+        description: "Joins random neighbouring cells that are not connected yet, growing \
+                      many small regions that merge into one maze.",
+        shapes: ALL_SHAPES,
+        factory: kruskal,
+    },
+    GeneratorEntry {
+        key: "recursive-backtracking",
+        name: "Recursive Backtracking",
+        description: "Walks at random to unvisited cells and backs up when stuck, which \
+                      makes long winding corridors.",
+        shapes: &[
+            Shape::Rect,
+            Shape::Hex,
+            Shape::Polar,
+            Shape::Delta,
+            Shape::Cylinder,
+            Shape::Torus,
+            Shape::Levels,
+        ],
+        factory: any_maze::<RecursiveBacktracking>,
+    },
+    GeneratorEntry {
+        key: "binary-tree",
+        name: "Binary Tree Algorithm",
+        description: "Links every cell to one of two fixed neighbours, which leaves two \
+                      open sides and a diagonal bias.",
+        shapes: &[Shape::Rect],
+        factory: |options| {
+            let generator =
+                BinaryTreeGenerator::from_maze_with_rng(options.rect_maze()?, options.rng());
+            Ok(AnyGenerator::Rect(Box::new(generator)))
+        },
+    },
+    GeneratorEntry {
+        key: "recursive-division",
+        name: "Recursive Division Algorithm",
+        description: "Starts from an empty room and splits it again and again with walls \
+                      that have a single gap.",
+        shapes: &[Shape::Rect],
+        factory: |options| {
+            // Recursive division starts from an empty maze
+            let [width, height] = options.size;
+            let maze = match &options.mask {
+                Some(mask) => Maze::with_mask(mask.clone(), false)?,
+                None => Maze::new_with_edges(width, height, false),
+            };
+            let generator = RecursiveDivisionGenerator::from_maze_with_rng(maze, options.rng());
+            Ok(AnyGenerator::Rect(Box::new(generator)))
+        },
+    },
+];
+
+/// The generator registered as `key`.
+pub fn find_generator(key: &str) -> Option<&'static GeneratorEntry> {
+    GENERATORS.iter().find(|entry| entry.key == key)
+}
+
+impl GeneratorEntry {
+    pub fn create(&self, options: &GeneratorOptions) -> Result<AnyGenerator, String> {
+        if !self.shapes.contains(&options.shape) {
+            return Err(format!("{} doesn't generate {} mazes", self.key, options.shape));
+        }
+
+        let [width, height] = options.size;
+        if options.mask.is_none() && (width < 2 || height < 2) {
+            return Err("Mazes must be at least 2x2".to_string());
+        }
+        if options.mask.is_some() && options.shape != Shape::Rect {
+            return Err(format!("Masks only shape rect mazes, not {} ones", options.shape));
+        }
+        let too_narrow = match options.shape {
+            Shape::Cylinder => width < 3,
+            Shape::Torus => width < 3 || height < 3,
+            _ => false,
+        };
+        if options.mask.is_none() && too_narrow {
+            return Err(format!("{} mazes must be at least 3 cells across", options.shape));
+        }
+
+        let counts = [("rings", options.rings), ("levels", options.levels)];
+        for (name, count) in counts {
+            if count.is_some_and(|count| count < 2) {
+                return Err(format!("Mazes must have at least 2 {}", name));
+            }
+        }
+
+        (self.factory)(options)
+    }
+}
+
+/// Generators that carve passages into any kind of maze.
+trait FromMaze {
+    fn from_maze<M: Topology + 'static>(maze: M, rng: StdRng) -> BoxedGenerator<M>;
+}
+
+struct Kruskal;
+
+impl FromMaze for Kruskal {
+    fn from_maze<M: Topology + 'static>(maze: M, rng: StdRng) -> BoxedGenerator<M> {
+        Box::new(KruskalsGenerator::from_maze_with_rng(maze, rng))
+    }
+}
+
+struct RecursiveBacktracking;
+
+impl FromMaze for RecursiveBacktracking {
+    fn from_maze<M: Topology + 'static>(maze: M, rng: StdRng) -> BoxedGenerator<M> {
+        Box::new(RecursiveBacktrackingGenerator::from_maze_with_rng(maze, rng))
+    }
+}
+
+fn kruskal(options: &GeneratorOptions) -> Result<AnyGenerator, String> {
+    let [width, height] = options.size;
+
+    match options.shape {
+        Shape::Weave => {
+            let crossings = options.crossings.unwrap_or(width * height / 6);
+            let rng = options.rng();
+            let generator = KruskalsGenerator::weave_with_rng(width, height, crossings, rng);
+            Ok(AnyGenerator::Weave(Box::new(generator)))
+        }
+        _ => any_maze::<Kruskal>(options),
+    }
+}
+
+fn any_maze<G: FromMaze>(options: &GeneratorOptions) -> Result<AnyGenerator, String> {
+    let [width, height] = options.size;
+    let rng = options.rng();
+
+    let generator = match options.shape {
+        Shape::Rect | Shape::Cylinder | Shape::Torus => {
+            AnyGenerator::Rect(G::from_maze(options.rect_maze()?, rng))
+        }
+        Shape::Hex => AnyGenerator::Hex(G::from_maze(HexMaze::new(width, height, true), rng)),
+        Shape::Polar => {
+            let rings = options.rings.unwrap_or((height / 2).max(2));
+            AnyGenerator::Polar(G::from_maze(PolarMaze::new(rings, true), rng))
+        }
+        Shape::Delta => {
+            AnyGenerator::Delta(G::from_maze(DeltaMaze::new(width, height, true), rng))
+        }
+        Shape::Weave => unreachable!("Only kruskal weaves mazes"),
+        Shape::Levels => {
+            let levels = options.levels.unwrap_or(3);
+            let maze = LevelMaze::new(width, height, levels, true);
+            AnyGenerator::Levels(G::from_maze(maze, rng))
+        }
+    };
+
+    Ok(generator)
+}
+
+#[cfg(test)]
+mod tests {
+    use nannou::rand::Rng;
+
+    use super::*;
+    use crate::generate::seed_generators;
+
+    #[test]
+    fn registry_names_match_the_generators() {
+        for entry in GENERATORS {
+            let generator = match entry.create(&GeneratorOptions::new(4, 4)).unwrap() {
+                AnyGenerator::Rect(generator) => generator,
+                _ => panic!("{} made a maze that is not rect", entry.key),
+            };
+
+            assert_eq!(generator.name(), entry.name);
+            assert_eq!(find_generator(entry.key).map(|found| found.key), Some(entry.key));
+        }
+    }
+
+    #[test]
+    fn every_generator_fills_a_mask() {
+        let mask = Mask::from_rendered_text("Hi", 16).unwrap();
+        let options = GeneratorOptions { mask: Some(mask), ..GeneratorOptions::new(2, 2) };
+
+        for entry in GENERATORS {
+            let mut generator = match entry.create(&options) {
+                Ok(AnyGenerator::Rect(generator)) => generator,
+                _ => panic!("{} can't fill a mask", entry.key),
+            };
+            let maze = generator.initial_maze();
+
+            assert!(generator.all(|(wall, _)| maze.is_enabled((wall.x, wall.y))));
+        }
+    }
+
+    #[test]
+    fn every_generator_counts_its_remaining_steps() {
+        let mask = Mask::from_rendered_text("Hi", 16).unwrap();
+        let masked = GeneratorOptions { mask: Some(mask), ..GeneratorOptions::new(2, 2) };
+
+        for options in [GeneratorOptions::new(7, 5), masked] {
+            for entry in GENERATORS {
+                for taken in [0, 3] {
+                    let mut generator = match entry.create(&options) {
+                        Ok(AnyGenerator::Rect(generator)) => generator,
+                        _ => unreachable!(),
+                    };
+                    generator.by_ref().take(taken).count();
+
+                    let remaining = generator.remaining_steps();
+                    assert_eq!(remaining, Some(generator.count()), "{}", entry.key);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn registry_checks_the_shape_and_size() {
+        let binary_tree = find_generator("binary-tree").unwrap();
+        let hex = GeneratorOptions { shape: Shape::Hex, ..GeneratorOptions::new(4, 4) };
+
+        assert!(binary_tree.create(&hex).is_err());
+        assert!(binary_tree.create(&GeneratorOptions::new(1, 4)).is_err());
+        assert!(matches!(
+            find_generator("kruskal").unwrap().create(&hex),
+            Ok(AnyGenerator::Hex(_))
+        ));
+        assert!(find_generator("nope").is_none());
+    }
+
+    #[test]
+    fn registry_rejects_options_no_maze_can_be_made_with() {
+        let kruskal = find_generator("kruskal").unwrap();
+        let options = |shape| GeneratorOptions { shape, ..GeneratorOptions::new(4, 4) };
+
+        let one_level = GeneratorOptions { levels: Some(1), ..options(Shape::Levels) };
+        let one_ring = GeneratorOptions { rings: Some(1), ..options(Shape::Polar) };
+        let masked_torus =
+            GeneratorOptions { mask: Some(Mask::new(4, 4)), ..options(Shape::Torus) };
+        let flat_mask = GeneratorOptions { mask: Some(Mask::new(4, 1)), ..options(Shape::Rect) };
+
+        assert!(kruskal.create(&one_level).is_err());
+        assert!(kruskal.create(&one_ring).is_err());
+        assert!(kruskal.create(&masked_torus).is_err());
+        assert!(kruskal.create(&flat_mask).is_err());
+        let narrow_cylinder = GeneratorOptions { size: [2, 4], ..options(Shape::Cylinder) };
+        let flat_torus = GeneratorOptions { size: [4, 2], ..options(Shape::Torus) };
+        assert!(kruskal.create(&narrow_cylinder).is_err());
+        assert!(kruskal.create(&flat_torus).is_err());
+        assert!(find_generator("recursive-division").unwrap().create(&flat_mask).is_err());
+    }
+
+    #[test]
+    fn seeded_generators_leave_the_others_alone() {
+        let seeded = GeneratorOptions { seed: Some(3), ..GeneratorOptions::new(5, 4) };
+        let walls = |entry: &GeneratorEntry| match entry.create(&seeded).unwrap() {
+            AnyGenerator::Rect(generator) => generator.collect::<Vec<_>>(),
+            _ => unreachable!(),
+        };
+
+        for entry in GENERATORS {
+            seed_generators(1);
+            let first = walls(entry);
+            let next_rng = generator_rng().gen::<u64>();
+
+            seed_generators(2);
+            assert_eq!(walls(entry), first, "{} ignores its seed", entry.key);
+
+            seed_generators(1);
+            assert_eq!(generator_rng().gen::<u64>(), next_rng, "{} reseeds", entry.key);
+        }
+    }
+}
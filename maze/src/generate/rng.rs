@@ -0,0 +1,37 @@
+use std::cell::RefCell;
+
+use nannou::rand::rngs::StdRng;
+use nannou::rand::{Rng, SeedableRng};
+
+thread_local! {
+    // Source of the seeds of the generators created on this thread
+    static SEEDS: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+/// Makes the generators created after this on the same thread produce the
+/// same mazes every time for the same `seed`.
+pub fn seed_generators(seed: u64) {
+    SEEDS.with(|seeds| *seeds.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+/// Random number generator for a new generator.
+pub(crate) fn generator_rng() -> StdRng {
+    SEEDS.with(|seeds| StdRng::seed_from_u64(seeds.borrow_mut().gen()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::RecursiveBacktrackingGenerator;
+
+    #[test]
+    fn seeded_generators_repeat_the_same_maze() {
+        let walls = |seed| {
+            seed_generators(seed);
+            RecursiveBacktrackingGenerator::new(6, 5).collect::<Vec<_>>()
+        };
+
+        assert_eq!(walls(7), walls(7));
+        assert_ne!(walls(7), walls(8));
+    }
+}
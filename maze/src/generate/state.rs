@@ -0,0 +1,18 @@
+/// Piece of a generator's internal state that can be shown while the maze
+/// is being generated.
+pub enum Highlight<C> {
+    /// Cell the generator is currently working from
+    ActiveCell(C),
+    /// Cells remembered for later, oldest first, e.g. a backtracking stack
+    Stack(Vec<C>),
+    /// Cell next to the gap left in a wall that is being laid
+    Gap(C),
+    /// Rectangle of cells the generator is currently working inside,
+    /// given by its corner cells
+    Field { top_left: C, bottom_right: C },
+}
+
+/// Optional introspection of a generator, see `MazeGenerator::state`.
+pub trait GeneratorState<C> {
+    fn highlights(&self) -> Vec<Highlight<C>>;
+}
@@ -0,0 +1,99 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use nannou::image::codecs::gif::{GifEncoder, Repeat};
+use nannou::image::{Delay, Frame, ImageResult};
+
+use crate::animate::Animator;
+use crate::raster::Rasterizer;
+
+pub struct GifConfig {
+    pub width: u32,
+    pub height: u32,
+    /// Size of the frames relative to the animation, e.g. 0.5 for a GIF
+    /// half the size of the window.
+    pub scale: f32,
+    /// Only every `frame_step`th frame of the animation is kept.
+    pub frame_step: usize,
+    pub frame_delay_ms: u32,
+    /// How long the last frame is shown before the GIF starts over.
+    pub end_delay_ms: u32,
+}
+
+/// Plays `animator` to the end, drawing its frames on the CPU, and writes
+/// them as a looping GIF. Returns the number of frames written.
+pub fn record_gif<W: Write>(
+    animator: &mut dyn Animator,
+    config: &GifConfig,
+    writer: W,
+) -> ImageResult<usize> {
+    // Quantizing the colours of every frame is slow, 10 is a good tradeoff
+    let mut encoder = GifEncoder::new_with_speed(writer, 10);
+    encoder.set_repeat(Repeat::Infinite)?;
+
+    let mut frame_count = 0;
+    for step in 0.. {
+        animator.update();
+
+        let last = animator.done();
+        if step % config.frame_step.max(1) != 0 && !last {
+            continue;
+        }
+
+        let raster = Rasterizer::with_scale(config.width, config.height, config.scale);
+        animator.draw(&raster, &raster.window_rect());
+
+        let delay = if last { config.end_delay_ms } else { config.frame_delay_ms };
+        let delay = Delay::from_numer_denom_ms(delay.max(config.frame_delay_ms), 1);
+        encoder.encode_frame(Frame::from_parts(raster.into_image(), 0, 0, delay))?;
+        frame_count += 1;
+
+        if last {
+            break;
+        }
+    }
+
+    Ok(frame_count)
+}
+
+pub fn save_gif<P: AsRef<Path>>(
+    animator: &mut dyn Animator,
+    config: &GifConfig,
+    path: P,
+) -> ImageResult<usize> {
+    let file = BufWriter::new(File::create(path)?);
+    record_gif(animator, config, file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animate::MazeAnimator;
+    use crate::generate::KruskalsGenerator;
+    use nannou::image::codecs::gif::GifDecoder;
+    use nannou::image::AnimationDecoder;
+
+    #[test]
+    fn record_gif_keeps_every_nth_frame_and_the_last() {
+        let mut animator = MazeAnimator::title(KruskalsGenerator::new(3, 3));
+        let config = GifConfig {
+            width: 64,
+            height: 48,
+            scale: 0.25,
+            frame_step: 10,
+            frame_delay_ms: 40,
+            end_delay_ms: 1000,
+        };
+
+        let mut gif = Vec::new();
+        let frame_count = record_gif(&mut animator, &config, &mut gif).unwrap();
+
+        // 48 frames of waiting, one to start, 8 walls carved and one to
+        // notice it is done make 58 frames, of which 0, 10, ..., 50 and 57
+        // are kept
+        let frames = GifDecoder::new(gif.as_slice()).unwrap().into_frames();
+        assert_eq!(frame_count, 7);
+        assert_eq!(frames.count(), frame_count);
+    }
+}
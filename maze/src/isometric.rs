@@ -0,0 +1,140 @@
+use nannou::color::IntoLinSrgba;
+use nannou::prelude::*;
+
+use crate::canvas::Canvas;
+use crate::maze::Topology;
+
+pub struct IsometricConfig {
+    pub floor_color: Rgb8,
+    pub wall_color: Rgb8,
+    /// Length of a cell side on the screen.
+    pub cell_size: f32,
+    /// Height and thickness of a full wall, in cells.
+    pub wall_height: f32,
+    pub wall_thickness: f32,
+}
+
+/// Draws a maze as walls standing on a floor, seen from above and to the
+/// front in an isometric projection.
+pub struct IsometricDraw<'a, M: Topology> {
+    draw: &'a dyn Canvas,
+    maze: &'a M,
+    config: &'a IsometricConfig,
+}
+
+impl<'a, M: Topology> IsometricDraw<'a, M> {
+    pub fn new(draw: &'a dyn Canvas, maze: &'a M, config: &'a IsometricConfig) -> Self {
+        IsometricDraw { draw, maze, config }
+    }
+
+    /// Position on the screen of `point` of the maze, `height` cells above
+    /// the floor. The back of the maze is its top right corner.
+    pub fn project(&self, point: Vec2, height: f32) -> Vec2 {
+        let (sin, cos) = deg_to_rad(30.0).sin_cos();
+        vec2((point.x - point.y) * cos, (point.x + point.y) * sin + height) * self.config.cell_size
+    }
+
+    pub fn floor(&self) {
+        for cell in self.maze.cells() {
+            let points: Vec<Vec2> = self.maze
+                .cell_outline(cell)
+                .into_iter()
+                .map(|point| self.project(point, 0.0))
+                .collect();
+
+            self.draw.polygon(&points, self.config.floor_color.into_lin_srgba());
+        }
+    }
+
+    /// Draws every wall raised to `raised(wall)` times the full height,
+    /// from the back so that walls in front cover the ones behind.
+    pub fn walls<F>(&self, raised: F)
+    where
+        F: Fn(&M::Wall) -> f32,
+    {
+        let mut segments: Vec<(Vec2, Vec2, f32)> = self.maze
+            .walls()
+            .map(|wall| (raised(&wall) * self.config.wall_height, wall))
+            .filter(|&(height, _)| height > 0.0)
+            .flat_map(|(height, wall)| {
+                let line = self.maze.wall_line(&wall);
+                (1..line.len()).map(move |i| (line[i - 1], line[i], height))
+            })
+            .collect();
+
+        let depth = |&(start, end, _): &(Vec2, Vec2, f32)| start.x + start.y + end.x + end.y;
+        segments.sort_by(|a, b| depth(b).total_cmp(&depth(a)));
+
+        for (start, end, height) in segments {
+            self.wall_segment(start, end, height);
+        }
+    }
+
+    /// Box around the segment from `start` to `end`, with only the faces
+    /// turned towards the viewer drawn.
+    fn wall_segment(&self, start: Vec2, end: Vec2, height: f32) {
+        let along = (end - start).normalize_or_zero() * self.config.wall_thickness / 2.0;
+        let across = vec2(-along.y, along.x);
+
+        // Lengthened by half the thickness so that walls meet at corners
+        let (start, end) = (start - along, end + along);
+        let footprint = [start - across, end - across, end + across, start + across];
+        let center = (start + end) / 2.0;
+
+        for side in 0..4 {
+            let (from, to) = (footprint[side], footprint[(side + 1) % 4]);
+            let mut normal = vec2(to.y - from.y, from.x - to.x).normalize_or_zero();
+            if normal.dot((from + to) / 2.0 - center) < 0.0 {
+                normal = -normal;
+            }
+
+            // The viewer is in front of the bottom left corner
+            if normal.dot(vec2(-1.0, -1.0)) <= 0.0 {
+                continue;
+            }
+
+            let points = [(from, 0.0), (to, 0.0), (to, height), (from, height)]
+                .map(|(point, height)| self.project(point, height));
+
+            // Faces turned to the left are lit, the ones to the right are
+            // in the shade
+            let light = 0.65 + 0.15 * normal.dot(vec2(-1.0, 1.0).normalize());
+            self.draw.polygon(&points, shade(self.config.wall_color, light).into_lin_srgba());
+        }
+
+        let top = footprint.map(|point| self.project(point, height));
+        self.draw.polygon(&top, self.config.wall_color.into_lin_srgba());
+    }
+}
+
+fn shade(color: Rgb8, light: f32) -> Rgb {
+    let channel = |value: u8| value as f32 / 255.0 * light;
+    rgb(channel(color.red), channel(color.green), channel(color.blue))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::Maze;
+
+    #[test]
+    fn project_puts_the_back_of_the_maze_at_the_top() {
+        let draw = nannou::Draw::new();
+        let maze = Maze::new(2, 2, true);
+        let config = IsometricConfig {
+            floor_color: rgb8(0, 0, 0),
+            wall_color: rgb8(0xff, 0xff, 0xff),
+            cell_size: 10.0,
+            wall_height: 1.0,
+            wall_thickness: 0.1,
+        };
+        let iso = IsometricDraw::new(&draw, &maze, &config);
+
+        let front = iso.project(vec2(-1.0, -1.0), 0.0);
+        let back = iso.project(vec2(1.0, 1.0), 0.0);
+
+        assert_eq!(front.x, back.x);
+        assert!(back.y > front.y);
+        assert!(iso.project(Vec2::ZERO, 1.0).y > iso.project(Vec2::ZERO, 0.0).y);
+    }
+}
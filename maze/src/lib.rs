@@ -1,5 +1,13 @@
 pub mod maze;
+pub mod canvas;
 pub mod draw;
+pub mod raster;
+pub mod svg;
+pub mod gif;
+pub mod isometric;
+pub mod offscreen;
+pub mod video;
 pub mod generate;
 pub mod animate;
-pub mod solve;
\ No newline at end of file
+pub mod solve;
+pub mod scene;
\ No newline at end of file
@@ -1,106 +1,301 @@
 use nannou::prelude::*;
+use std::cell::RefCell;
 use std::collections::VecDeque;
-use std::path::PathBuf;
+use std::env;
+use std::path::{Path, PathBuf};
 use std::process::exit;
+use std::sync::OnceLock;
 use std::time::SystemTime;
 use std::time::UNIX_EPOCH;
 
 use maze::animate::*;
-use maze::generate::*;
+use maze::gif::{save_gif, GifConfig};
+use maze::offscreen::OffscreenRenderer;
+use maze::raster::Rasterizer;
+use maze::generate::{find_generator, GENERATORS};
+use maze::scene::{Scene, Timeline};
+use maze::video::{encode_frames, FrameWriter};
 
-const MAZE_WIDTH: usize = 38;
-const MAZE_HEIGHT: usize = 20;
-const SCENE_TIMEOUT: u32 = 60;
-const EXPORTING: bool = false;
+mod cli;
+
+use cli::{Command, Options};
+
+const DEFAULT_SCENES: &str = include_str!("../scenes/default.toml");
+
+// nannou's model and update functions can't capture the options
+static OPTIONS: OnceLock<Options> = OnceLock::new();
+
+thread_local! {
+    // Scenes for the model function, built up front to report invalid
+    // scenes before opening the window
+    static SCENES: RefCell<VecDeque<Box<dyn Animator>>> = RefCell::new(VecDeque::new());
+}
 
 struct Model {
     animators: VecDeque<Box<dyn Animator>>,
     current_animator: Box<dyn Animator>,
-    frames_folder: PathBuf,
+    // Renders the window's frames again into the video, when exporting
+    offscreen: Option<OffscreenRenderer>,
 }
 
 fn main() {
-    nannou::app(model).update(update).run();
+    let command = cli::parse(env::args().skip(1)).unwrap_or_else(|error| {
+        eprintln!("{}\n\n{}", error, cli::USAGE);
+        exit(2)
+    });
+
+    match command {
+        Command::Help => println!("{}", cli::USAGE),
+        Command::Generators => {
+            for entry in GENERATORS {
+                let shapes: Vec<String> = entry.shapes.iter().map(ToString::to_string).collect();
+                println!("{} ({})", entry.key, entry.name);
+                println!("    {}", entry.description);
+                println!("    Shapes: {}\n", shapes.join(", "));
+            }
+        }
+        // Encodes the frames saved while exporting into a video
+        Command::Encode { frames_folder, video, encoder } => {
+            let frame_count = encode_frames(&encoder, &frames_folder, &video)
+                .expect("Could not encode the frames");
+            println!("Encoded {} frames", frame_count);
+        }
+        Command::Gif { file, options } => {
+            let scenes = scenes(&options);
+            let [width, height] = options.window_size;
+            // Half the size of the video and every other frame keep GIFs small
+            let config = GifConfig {
+                width: width / 2,
+                height: height / 2,
+                scale: 0.5,
+                frame_step: 2,
+                frame_delay_ms: 2000 / options.encoder.frame_rate.max(1),
+                end_delay_ms: 1000,
+            };
+
+            let frame_count = save_gif(&mut SequenceAnimator::new(scenes), &config, &file)
+                .expect("Could not save the GIF");
+            println!("Recorded {} frames", frame_count);
+        }
+        Command::Svg { file, animated, options } => {
+            let step_duration = 1.0 / options.encoder.frame_rate.max(1) as f32;
+            let svg = timeline(&options)
+                .and_then(|timeline| timeline.svg(animated, step_duration))
+                .unwrap_or_else(|error| {
+                    eprintln!("{}", error);
+                    exit(2)
+                });
+
+            std::fs::write(&file, svg).expect("Could not save the SVG");
+        }
+        Command::Run(options) => {
+            let scenes = scenes(&options);
+
+            if options.software {
+                render_frames(&options, scenes);
+            } else if options.headless {
+                render_headless(&options, scenes).unwrap_or_else(|error| {
+                    eprintln!("{}", error);
+                    exit(1)
+                });
+            } else {
+                SCENES.with(|cell| *cell.borrow_mut() = scenes);
+                OPTIONS.get_or_init(|| options);
+                nannou::app(model).update(update).run();
+            }
+        }
+    }
+}
+
+/// The scenes to play: the generators given on the command line, a scene
+/// file or the default video, with the other options on top.
+fn timeline(options: &Options) -> Result<Timeline, String> {
+    let mut timeline = match (&options.generators, &options.scene_file) {
+        (Some(_), Some(_)) => return Err("--generators and --scenes don't go together".to_string()),
+        (Some(names), None) => generator_timeline(options, names)?,
+        (None, Some(path)) => Timeline::load(path)?,
+        (None, None) => Timeline::from_toml(DEFAULT_SCENES)?,
+    };
+
+    if let Some((width, height)) = options.maze_size {
+        timeline.size = [width, height];
+    }
+    if let Some(wait) = options.scene_wait {
+        timeline.wait = wait;
+    }
+    if options.seed.is_some() {
+        timeline.seed = options.seed;
+    }
+    timeline.frame_size = options.window_size;
+
+    Ok(timeline)
 }
 
-fn add_generator<T: 'static>(
-    animators: &mut VecDeque<Box<dyn Animator>>,
-    generator: T,
-    start: (usize, usize),
-    end: (usize, usize),
-) where
-    T: MazeGenerator,
+/// Animators of the scenes to play. Exits if the scenes are invalid.
+fn scenes(options: &Options) -> VecDeque<Box<dyn Animator>> {
+    timeline(options)
+        .and_then(|timeline| timeline.animators())
+        .unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            exit(2)
+        })
+}
+
+/// A rectangular maze for each of the generators given on the command line.
+fn generator_timeline(options: &Options, names: &[String]) -> Result<Timeline, String> {
+    let cell = |(x, y): (usize, usize)| vec![x as i64, y as i64];
+
+    let scenes = names
+        .iter()
+        .map(|name| {
+            if find_generator(name).is_none() {
+                return Err(format!("Unknown generator {}", name));
+            }
+
+            Ok(Scene {
+                generator: Some(name.clone()),
+                start: options.start.map(cell),
+                end: options.end.map(cell),
+                ..Scene::default()
+            })
+        })
+        .collect::<Result<_, String>>()?;
+
+    Ok(Timeline { scenes, ..Timeline::default() })
+}
+
+fn render_frames(options: &Options, scenes: VecDeque<Box<dyn Animator>>) {
+    let output_folder = options
+        .export_folder
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")));
+    let mut writer = get_frame_writer(options, &output_folder);
+
+    let [width, height] = options.window_size;
+    let frame_count = play(scenes, |frame, animator| {
+        let raster = Rasterizer::new(width, height);
+        animator.draw(&raster, &raster.window_rect());
+        writer
+            .write_frame(frame, &raster.into_image())
+            .expect("Could not write frame");
+    });
+
+    writer.finish().expect("Could not finish writing the frames");
+    println!("Rendered {} frames", frame_count);
+}
+
+/// Renders the scenes on the GPU without a window, as fast as it goes
+/// rather than at the display's refresh rate.
+fn render_headless(options: &Options, scenes: VecDeque<Box<dyn Animator>>) -> Result<(), String> {
+    let output_folder = options
+        .export_folder
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")));
+    let writer = get_frame_writer(options, &output_folder);
+    let mut offscreen = OffscreenRenderer::headless(options.window_size, writer)?;
+
+    let frame_count = play(scenes, |_, animator| {
+        let draw = Draw::new();
+        animator.draw(&draw, &offscreen.rect());
+        offscreen.render(&draw);
+    });
+
+    offscreen.finish();
+    println!("Rendered {} frames", frame_count);
+    Ok(())
+}
+
+/// Plays the scenes one after the other, passing each frame to `render`
+/// with its index, and returns the number of frames.
+fn play<F>(scenes: VecDeque<Box<dyn Animator>>, mut render: F) -> usize
+where
+    F: FnMut(usize, &dyn Animator),
 {
-    let animator: Box<dyn Animator> = Box::new(MazeAnimator::new(generator, start, end));
-    animators.push_back(animator);
-    animators.push_back(Box::new(WaitingAnimator::new(SCENE_TIMEOUT)));
+    let mut frame_count = 0;
+
+    for mut animator in scenes {
+        loop {
+            animator.update();
+            render(frame_count, animator.as_ref());
+            frame_count += 1;
+
+            if animator.done() {
+                break;
+            }
+        }
+    }
+
+    frame_count
 }
 
-fn get_frames_folder(app: &App) -> PathBuf {
-    let timestamp = SystemTime::now()
+fn get_timestamp() -> u128 {
+    SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .expect("Could not get timestamp")
-        .as_millis();
+        .as_millis()
+}
+
+/// Writer encoding the frames into a video in `output_folder`, or saving
+/// them into a frames folder if the encoder is missing.
+fn get_frame_writer(options: &Options, output_folder: &Path) -> FrameWriter {
+    let timestamp = get_timestamp();
+    let video = output_folder.join(format!("maze_{}.mp4", timestamp));
+    let frames_folder = output_folder.join(format!("frames_{}", timestamp));
 
-    app.project_path()
-        .expect("Could not get project_path")
-        .join(format!("frames_{}", timestamp))
+    std::fs::create_dir_all(output_folder).expect("Could not create the output folder");
+    FrameWriter::new(&options.encoder, options.window_size, &video, frames_folder)
+        .expect("Could not start the encoder")
 }
 
 fn model(app: &App) -> Model {
-    app.new_window()
-        .size(1280, 720)
+    let options = OPTIONS.get().expect("Options are set before running the app");
+    let [width, height] = options.window_size;
+    let window = app.new_window()
+        .size(width, height)
         .resizable(false)
         .decorations(false)
         .view(view)
         .build()
         .expect("Could not initialize window");
 
-    let mut animators = VecDeque::new();
-
-    add_generator(
-        &mut animators,
-        RecursiveBacktrackingGenerator::new(MAZE_WIDTH, MAZE_HEIGHT),
-        (0, 0),
-        (MAZE_WIDTH - 1, MAZE_HEIGHT - 1),
-    );
-    add_generator(
-        &mut animators,
-        KruskalsGenerator::new(MAZE_WIDTH, MAZE_HEIGHT),
-        (MAZE_WIDTH - 1, MAZE_HEIGHT - 1),
-        (0, 0),
-    );
-    add_generator(
-        &mut animators,
-        BinaryTreeGenerator::new(MAZE_WIDTH, MAZE_HEIGHT),
-        (0, 0),
-        (MAZE_WIDTH - 1, MAZE_HEIGHT - 1),
-    );
-    add_generator(
-        &mut animators,
-        RecursiveDivisionGenerator::new(MAZE_WIDTH, MAZE_HEIGHT),
-        (MAZE_WIDTH - 1, MAZE_HEIGHT - 1),
-        (0, 0),
-    );
+    let offscreen = options.export_folder.as_ref().map(|folder| {
+        let window = app.window(window).expect("Could not get window");
+        let writer = get_frame_writer(options, folder);
+        let device = window.device_queue_pair().clone();
+        OffscreenRenderer::new(device, window.msaa_samples(), options.window_size, writer)
+    });
 
+    let mut animators = SCENES.with(|cell| cell.take());
     let current_animator = animators.pop_front().unwrap();
 
     Model {
         animators,
         current_animator,
-        frames_folder: get_frames_folder(app),
+        offscreen,
     }
 }
 
 fn update(app: &App, model: &mut Model, _update: Update) {
     if model.current_animator.done() {
         model.current_animator = model.animators.pop_front().unwrap_or_else(|| {
+            if let Some(offscreen) = model.offscreen.take() {
+                let frame_count = offscreen.frame_count();
+                offscreen.finish();
+                println!("Exported {} frames", frame_count);
+            }
+
             app.quit();
             exit(0)
         });
     }
 
     model.current_animator.update();
+
+    if let Some(offscreen) = model.offscreen.as_mut() {
+        let draw = Draw::new();
+        model.current_animator.draw(&draw, &offscreen.rect());
+        offscreen.render(&draw);
+    }
 }
 
 fn view(app: &App, model: &Model, frame: Frame) {
@@ -110,10 +305,4 @@ fn view(app: &App, model: &Model, frame: Frame) {
     model.current_animator.draw(&draw, &window);
 
     draw.to_frame(app, &frame).unwrap();
-
-    if EXPORTING {
-        let file = format!("frame_{}.png", frame.nth());
-        app.main_window()
-            .capture_frame(model.frames_folder.join(file));
-    }
 }
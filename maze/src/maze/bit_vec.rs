@@ -0,0 +1,83 @@
+use std::mem::size_of;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// Fixed length sequence of bits packed into a single allocation.
+#[derive(Clone)]
+pub struct BitVec {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitVec {
+    pub fn new(len: usize, value: bool) -> BitVec {
+        let word = if value { u64::MAX } else { 0 };
+        let words = vec![word; len.div_ceil(WORD_BITS)];
+
+        BitVec { words, len }
+    }
+
+    #[inline]
+    pub fn get(&self, index: usize) -> bool {
+        debug_assert!(index < self.len, "Bit index {} out of range {}", index, self.len);
+
+        self.words[index / WORD_BITS] & (1 << (index % WORD_BITS)) != 0
+    }
+
+    #[inline]
+    pub fn set(&mut self, index: usize, value: bool) {
+        debug_assert!(index < self.len, "Bit index {} out of range {}", index, self.len);
+
+        let word = &mut self.words[index / WORD_BITS];
+        let mask = 1 << (index % WORD_BITS);
+
+        if value {
+            *word |= mask;
+        } else {
+            *word &= !mask;
+        }
+    }
+
+    /// Number of bytes allocated on the heap for the bits.
+    pub fn heap_size(&self) -> usize {
+        self.words.capacity() * size_of::<u64>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bit_vec_is_constructed_with_the_given_value() {
+        let bits = BitVec::new(100, true);
+
+        assert!((0..100).all(|i| bits.get(i)));
+    }
+
+    #[test]
+    fn bit_vec_set_changes_only_one_bit() {
+        let mut bits = BitVec::new(130, false);
+        bits.set(64, true);
+
+        let on: Vec<usize> = (0..130).filter(|&i| bits.get(i)).collect();
+        assert_eq!(on, vec![64]);
+
+        bits.set(64, false);
+        assert!(!bits.get(64));
+    }
+
+    #[test]
+    fn bit_vec_uses_one_bit_per_value() {
+        let bits = BitVec::new(1024, false);
+        assert_eq!(bits.heap_size(), 1024 / 8);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic]
+    fn bit_vec_get_panics_past_the_end() {
+        let bits = BitVec::new(10, false);
+        bits.get(10);
+    }
+}
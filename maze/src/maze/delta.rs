@@ -0,0 +1,241 @@
+use nannou::prelude::*;
+
+use super::bit_vec::BitVec;
+use super::Topology;
+
+use DeltaSide::*;
+
+/// Side of a triangular cell. The base is the horizontal side, which is at
+/// the bottom of upward pointing cells and at the top of downward ones.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DeltaSide { Left, Right, Base }
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DeltaWall {
+    pub x: usize,
+    pub y: usize,
+    pub side: DeltaSide,
+}
+
+/// Maze of triangles in rows. Cell `(x, y)` points upward when `x + y` is
+/// even and downward otherwise, so every cell has at most three neighbours.
+#[derive(Clone)]
+pub struct DeltaMaze {
+    width: usize,
+    height: usize,
+    // Right wall and base of every cell, with an extra column before the
+    // maze for the left edge. Upward cells store the base they share with
+    // the downward cell below them.
+    walls: BitVec,
+}
+
+impl DeltaMaze {
+    pub fn new(width: usize, height: usize, walls_on: bool) -> DeltaMaze {
+        assert!(width > 1);
+        assert!(height > 1);
+
+        DeltaMaze {
+            width,
+            height,
+            walls: BitVec::new((width + 1) * height * 2, walls_on),
+        }
+    }
+
+    pub fn width(&self) -> usize { self.width }
+
+    pub fn height(&self) -> usize { self.height }
+
+    pub fn points_up(&self, (x, y): (usize, usize)) -> bool {
+        (x + y) % 2 == 0
+    }
+
+    pub fn get_wall(&self, wall: &DeltaWall) -> bool {
+        self.walls.get(self.wall_index(wall))
+    }
+
+    pub fn set_wall(&mut self, wall: &DeltaWall, on: bool) {
+        let index = self.wall_index(wall);
+        self.walls.set(index, on);
+    }
+
+    fn wall_index(&self, wall: &DeltaWall) -> usize {
+        let &DeltaWall { x, y, side } = wall;
+        assert!(x < self.width && y < self.height);
+
+        let slot = |x: usize, y: usize, side: usize| (y * (self.width + 1) + x) * 2 + side;
+
+        match side {
+            Left => slot(x, y, 0),
+            Right => slot(x + 1, y, 0),
+            Base if !self.points_up((x, y)) && y > 0 => slot(x + 1, y - 1, 1),
+            Base => slot(x + 1, y, 1),
+        }
+    }
+
+    fn row_height() -> f32 {
+        3.0.sqrt() / 2.0
+    }
+
+    /// Corners of the cell, in the order apex, then the base corners from
+    /// left to right.
+    fn corners(&self, cell: (usize, usize)) -> [Vec2; 3] {
+        let (x, y) = cell;
+        let row_height = DeltaMaze::row_height();
+        let top_left = vec2(-self.size().x / 2.0, self.size().y / 2.0);
+
+        let left = x as f32 * 0.5;
+        let top = -(y as f32) * row_height;
+        let bottom = top - row_height;
+
+        let [apex, base_left, base_right] = if self.points_up(cell) {
+            [(left + 0.5, top), (left, bottom), (left + 1.0, bottom)]
+        } else {
+            [(left + 0.5, bottom), (left, top), (left + 1.0, top)]
+        };
+
+        [apex, base_left, base_right].map(|(x, y)| top_left + vec2(x, y))
+    }
+}
+
+impl Topology for DeltaMaze {
+    type Cell = (usize, usize);
+    type Wall = DeltaWall;
+
+    fn cells(&self) -> Box<dyn Iterator<Item = (usize, usize)> + '_> {
+        Box::new(itertools::iproduct!(0..self.height, 0..self.width).map(|(y, x)| (x, y)))
+    }
+
+    fn cell_count(&self) -> usize {
+        self.width * self.height
+    }
+
+    fn cell_index(&self, (x, y): (usize, usize)) -> usize {
+        y * self.width + x
+    }
+
+    fn neighbours(&self, (x, y): (usize, usize)) -> Vec<((usize, usize), DeltaWall)> {
+        let mut neighbours = Vec::with_capacity(3);
+
+        if x > 0 {
+            neighbours.push(((x - 1, y), DeltaWall { x, y, side: Left }));
+        }
+        if x < self.width - 1 {
+            neighbours.push(((x + 1, y), DeltaWall { x, y, side: Right }));
+        }
+
+        let base = DeltaWall { x, y, side: Base };
+        if self.points_up((x, y)) && y < self.height - 1 {
+            neighbours.push(((x, y + 1), base));
+        } else if !self.points_up((x, y)) && y > 0 {
+            neighbours.push(((x, y - 1), base));
+        }
+
+        neighbours
+    }
+
+    fn walls(&self) -> Box<dyn Iterator<Item = DeltaWall> + '_> {
+        let walls = self.cells().flat_map(move |(x, y)| {
+            let left = (x == 0).then_some(Left);
+            // Downward cells share their base with the upward cell above
+            let base = (self.points_up((x, y)) || y == 0).then_some(Base);
+
+            left.into_iter()
+                .chain([Right])
+                .chain(base)
+                .map(move |side| DeltaWall { x, y, side })
+        });
+
+        Box::new(walls)
+    }
+
+    fn get_wall(&self, wall: &DeltaWall) -> bool {
+        DeltaMaze::get_wall(self, wall)
+    }
+
+    fn set_wall(&mut self, wall: &DeltaWall, on: bool) {
+        DeltaMaze::set_wall(self, wall, on)
+    }
+
+    fn size(&self) -> Vec2 {
+        vec2((self.width + 1) as f32 * 0.5, self.height as f32 * DeltaMaze::row_height())
+    }
+
+    fn cell_center(&self, cell: (usize, usize)) -> Vec2 {
+        let [apex, base_left, base_right] = self.corners(cell);
+        (apex + base_left + base_right) / 3.0
+    }
+
+    fn cell_outline(&self, cell: (usize, usize)) -> Vec<Vec2> {
+        self.corners(cell).to_vec()
+    }
+
+    fn wall_line(&self, wall: &DeltaWall) -> Vec<Vec2> {
+        let [apex, base_left, base_right] = self.corners((wall.x, wall.y));
+
+        match wall.side {
+            Left => vec![apex, base_left],
+            Right => vec![apex, base_right],
+            Base => vec![base_left, base_right],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delta_maze_cells_have_at_most_three_neighbours() {
+        let maze = DeltaMaze::new(6, 4, true);
+
+        assert!(maze.cells().all(|cell| maze.neighbours(cell).len() <= 3));
+        assert_eq!(maze.neighbours((2, 2)).len(), 3);
+        assert_eq!(maze.neighbours((3, 2)).len(), 3);
+    }
+
+    #[test]
+    fn delta_maze_walls_are_stored_separately() {
+        let mut maze = DeltaMaze::new(4, 3, false);
+        let walls: Vec<DeltaWall> = maze.walls().collect();
+
+        // Three sides per cell, minus the ones shared between two cells
+        assert_eq!(walls.len(), 4 * 3 * 3 - (3 * 3 + 2 * 2));
+
+        for wall in &walls {
+            maze.set_wall(wall, true);
+
+            assert_eq!(walls.iter().filter(|wall| maze.get_wall(wall)).count(), 1);
+            maze.set_wall(wall, false);
+        }
+    }
+
+    #[test]
+    fn delta_maze_neighbours_share_walls() {
+        let mut maze = DeltaMaze::new(4, 3, true);
+
+        for cell in maze.cells().collect::<Vec<_>>() {
+            for (neighbour, wall) in maze.neighbours(cell) {
+                maze.set_wall(&wall, false);
+
+                assert!(maze.open_neighbours(neighbour).contains(&cell));
+                maze.set_wall(&wall, true);
+            }
+        }
+    }
+
+    #[test]
+    fn delta_maze_wall_lines_touch_both_cells() {
+        let maze = DeltaMaze::new(4, 3, true);
+
+        for cell in [(1, 1), (2, 1)] {
+            for (neighbour, wall) in maze.neighbours(cell) {
+                let line = maze.wall_line(&wall);
+                let shared = maze.cell_outline(neighbour);
+
+                for point in line {
+                    assert!(shared.iter().any(|corner| corner.distance(point) < 1e-4));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use nannou::prelude::*;
+
+use super::bit_vec::BitVec;
+use super::Topology;
+
+use HexDirection::*;
+
+/// Side of a pointy-top hexagonal cell.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum HexDirection { East, NorthEast, NorthWest, West, SouthWest, SouthEast }
+
+const DIRECTIONS: [HexDirection; 6] = [East, NorthEast, NorthWest, West, SouthWest, SouthEast];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct HexWall {
+    pub x: usize,
+    pub y: usize,
+    pub dir: HexDirection,
+}
+
+/// Maze of pointy-top hexagons in rows, with every odd row shifted right by
+/// half a cell.
+#[derive(Clone)]
+pub struct HexMaze {
+    width: usize,
+    height: usize,
+    // East, south-east and south-west wall of every cell, with an extra
+    // row above and a column on both sides of the maze for the edges
+    walls: BitVec,
+}
+
+impl HexMaze {
+    pub fn new(width: usize, height: usize, walls_on: bool) -> HexMaze {
+        assert!(width > 1);
+        assert!(height > 1);
+
+        HexMaze {
+            width,
+            height,
+            walls: BitVec::new((width + 2) * (height + 1) * 3, walls_on),
+        }
+    }
+
+    pub fn width(&self) -> usize { self.width }
+
+    pub fn height(&self) -> usize { self.height }
+
+    pub fn get_wall(&self, wall: &HexWall) -> bool {
+        self.walls.get(self.wall_index(wall))
+    }
+
+    pub fn set_wall(&mut self, wall: &HexWall, on: bool) {
+        let index = self.wall_index(wall);
+        self.walls.set(index, on);
+    }
+
+    /// Neighbouring position in `dir`, which can be outside of the maze.
+    fn step(x: isize, y: isize, dir: HexDirection) -> (isize, isize) {
+        // Odd rows are shifted right, so their diagonal neighbours are too
+        let shift = y.rem_euclid(2);
+
+        match dir {
+            East => (x + 1, y),
+            West => (x - 1, y),
+            NorthEast => (x + shift, y - 1),
+            NorthWest => (x + shift - 1, y - 1),
+            SouthEast => (x + shift, y + 1),
+            SouthWest => (x + shift - 1, y + 1),
+        }
+    }
+
+    fn contains(&self, (x, y): (isize, isize)) -> bool {
+        (0..self.width as isize).contains(&x) && (0..self.height as isize).contains(&y)
+    }
+
+    fn wall_index(&self, wall: &HexWall) -> usize {
+        assert!(wall.x < self.width && wall.y < self.height);
+
+        let (x, y) = (wall.x as isize, wall.y as isize);
+
+        // Walls are stored with the cell they are east, south-east or
+        // south-west of
+        let ((x, y), side) = match wall.dir {
+            East => ((x, y), 0),
+            SouthEast => ((x, y), 1),
+            SouthWest => ((x, y), 2),
+            West => (HexMaze::step(x, y, West), 0),
+            NorthWest => (HexMaze::step(x, y, NorthWest), 1),
+            NorthEast => (HexMaze::step(x, y, NorthEast), 2),
+        };
+
+        let (x, y) = ((x + 1) as usize, (y + 1) as usize);
+        (y * (self.width + 2) + x) * 3 + side
+    }
+
+    fn radius() -> f32 {
+        // Cells are one unit wide between their east and west sides
+        1.0 / 3.0.sqrt()
+    }
+}
+
+impl Topology for HexMaze {
+    type Cell = (usize, usize);
+    type Wall = HexWall;
+
+    fn cells(&self) -> Box<dyn Iterator<Item = (usize, usize)> + '_> {
+        Box::new(itertools::iproduct!(0..self.height, 0..self.width).map(|(y, x)| (x, y)))
+    }
+
+    fn cell_count(&self) -> usize {
+        self.width * self.height
+    }
+
+    fn cell_index(&self, (x, y): (usize, usize)) -> usize {
+        y * self.width + x
+    }
+
+    fn neighbours(&self, (x, y): (usize, usize)) -> Vec<((usize, usize), HexWall)> {
+        DIRECTIONS
+            .into_iter()
+            .map(|dir| (HexMaze::step(x as isize, y as isize, dir), dir))
+            .filter(|&(neighbour, _)| self.contains(neighbour))
+            .map(|((nx, ny), dir)| ((nx as usize, ny as usize), HexWall { x, y, dir }))
+            .collect()
+    }
+
+    fn walls(&self) -> Box<dyn Iterator<Item = HexWall> + '_> {
+        // A wall belongs to the cell it is east, south-east or south-west
+        // of, unless that cell is outside of the maze
+        let walls = self.cells().flat_map(move |(x, y)| {
+            DIRECTIONS
+                .into_iter()
+                .filter(move |&dir| {
+                    matches!(dir, East | SouthEast | SouthWest)
+                        || !self.contains(HexMaze::step(x as isize, y as isize, dir))
+                })
+                .map(move |dir| HexWall { x, y, dir })
+        });
+
+        Box::new(walls)
+    }
+
+    fn get_wall(&self, wall: &HexWall) -> bool {
+        HexMaze::get_wall(self, wall)
+    }
+
+    fn set_wall(&mut self, wall: &HexWall, on: bool) {
+        HexMaze::set_wall(self, wall, on)
+    }
+
+    fn size(&self) -> Vec2 {
+        let radius = HexMaze::radius();
+
+        vec2(
+            self.width as f32 + 0.5,
+            (self.height - 1) as f32 * 1.5 * radius + 2.0 * radius,
+        )
+    }
+
+    fn cell_center(&self, (x, y): (usize, usize)) -> Vec2 {
+        let radius = HexMaze::radius();
+        let shift = (y % 2) as f32 * 0.5;
+        let top_left = vec2(-self.size().x / 2.0, self.size().y / 2.0);
+
+        top_left + vec2(x as f32 + shift + 0.5, -(y as f32 * 1.5 * radius + radius))
+    }
+
+    fn cell_outline(&self, cell: (usize, usize)) -> Vec<Vec2> {
+        let center = self.cell_center(cell);
+
+        // Starting from the lower end of the east side, counter-clockwise
+        (0..6)
+            .map(|corner| deg_to_rad(60.0 * corner as f32 - 30.0))
+            .map(|angle| center + vec2(angle.cos(), angle.sin()) * HexMaze::radius())
+            .collect()
+    }
+
+    fn wall_line(&self, wall: &HexWall) -> Vec<Vec2> {
+        let corners = self.cell_outline((wall.x, wall.y));
+        let side = DIRECTIONS.iter().position(|&dir| dir == wall.dir).unwrap();
+
+        vec![corners[side], corners[(side + 1) % 6]]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_maze_inner_cell_has_six_neighbours() {
+        let maze = HexMaze::new(4, 4, true);
+
+        assert_eq!(maze.neighbours((1, 1)).len(), 6);
+        assert_eq!(maze.neighbours((2, 2)).len(), 6);
+    }
+
+    #[test]
+    fn hex_maze_neighbours_share_walls() {
+        let mut maze = HexMaze::new(4, 4, true);
+
+        for cell in maze.cells().collect::<Vec<_>>() {
+            for (neighbour, wall) in maze.neighbours(cell) {
+                maze.set_wall(&wall, false);
+
+                assert!(maze.open_neighbours(neighbour).contains(&cell));
+                maze.set_wall(&wall, true);
+            }
+        }
+    }
+
+    #[test]
+    fn hex_maze_walls_are_listed_once() {
+        let maze = HexMaze::new(3, 2, true);
+
+        let lines: Vec<Vec<Vec2>> = maze.walls().map(|wall| maze.wall_line(&wall)).collect();
+        let distinct = lines.iter().enumerate().all(|(i, line)| {
+            lines[..i].iter().all(|other| {
+                let same = |a: Vec2, b: Vec2| a.distance(b) < 1e-4;
+                !(same(line[0], other[1]) && same(line[1], other[0])
+                    || same(line[0], other[0]) && same(line[1], other[1]))
+            })
+        });
+
+        // 6 sides per cell, minus the ones shared between two cells
+        let shared = 2 * 2 + 5;
+        assert!(distinct);
+        assert_eq!(lines.len(), 6 * 6 - shared);
+    }
+
+    #[test]
+    fn hex_maze_wall_lines_touch_both_cells() {
+        let maze = HexMaze::new(3, 3, true);
+
+        for (neighbour, wall) in maze.neighbours((1, 1)) {
+            let line = maze.wall_line(&wall);
+            let middle = (line[0] + line[1]) / 2.0;
+            let centers = (maze.cell_center((1, 1)) + maze.cell_center(neighbour)) / 2.0;
+
+            assert!(middle.distance(centers) < 1e-4);
+        }
+    }
+
+    #[test]
+    fn hex_maze_walls_are_stored_separately() {
+        let mut maze = HexMaze::new(3, 4, false);
+        let walls: Vec<HexWall> = maze.walls().collect();
+
+        for wall in &walls {
+            maze.set_wall(wall, true);
+
+            assert_eq!(walls.iter().filter(|wall| maze.get_wall(wall)).count(), 1);
+            maze.set_wall(wall, false);
+        }
+    }
+}
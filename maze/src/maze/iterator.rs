@@ -6,9 +6,13 @@ pub struct WallIterator<'a> {
 }
 
 impl<'a> WallIterator<'a> {
-    pub fn new(maze: &'a Maze) -> WallIterator {
-        let left_edge_iter = (0..maze.height()).map(|y| Wall { x: 0, y, dir: Left });
-        let top_edge_iter = (0..maze.width()).map(|x| Wall { x, y: 0, dir: Up });
+    pub fn new(maze: &'a Maze) -> WallIterator<'a> {
+        // Joined edges are already covered by the right and bottom walls
+        let left_edge_height = if maze.wrap().horizontal() { 0 } else { maze.height() };
+        let top_edge_width = if maze.wrap().vertical() { 0 } else { maze.width() };
+
+        let left_edge_iter = (0..left_edge_height).map(|y| Wall { x: 0, y, dir: Left });
+        let top_edge_iter = (0..top_edge_width).map(|x| Wall { x, y: 0, dir: Up });
         let right_iter = itertools::iproduct!(0..maze.width(), 0..maze.height())
             .map(|(x, y)| Wall { x, y, dir: Right });
         let down_iter = itertools::iproduct!(0..maze.width(), 0..maze.height())
@@ -0,0 +1,238 @@
+use nannou::prelude::*;
+
+use super::bit_vec::BitVec;
+use super::{Maze, Topology, Wall};
+
+/// Wall of a multi-level maze: either a wall within a level, or the stairs
+/// from `(x, y)` on `level` to the same place on the level above, which
+/// are off when they can be taken.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum LevelWall {
+    Side { level: usize, wall: Wall },
+    Stairs { x: usize, y: usize, level: usize },
+}
+
+/// Stack of square grid mazes joined by stairs. Cells are `(x, y, level)`
+/// and levels are drawn side by side, from the lowest on the left.
+#[derive(Clone)]
+pub struct LevelMaze {
+    levels: Vec<Maze>,
+    // Stairs from every cell to the one above it, level by level
+    stairs: BitVec,
+}
+
+// Space between two levels, in cells
+const LEVEL_GAP: f32 = 1.0;
+
+impl LevelMaze {
+    pub fn new(width: usize, height: usize, levels: usize, walls_on: bool) -> LevelMaze {
+        assert!(levels > 1);
+
+        LevelMaze {
+            levels: vec![Maze::new(width, height, walls_on); levels],
+            stairs: BitVec::new(width * height * (levels - 1), walls_on),
+        }
+    }
+
+    pub fn width(&self) -> usize { self.levels[0].width() }
+
+    pub fn height(&self) -> usize { self.levels[0].height() }
+
+    pub fn levels(&self) -> usize { self.levels.len() }
+
+    pub fn get_wall(&self, wall: &LevelWall) -> bool {
+        match *wall {
+            LevelWall::Side { level, wall } => self.levels[level].get_wall(&wall),
+            LevelWall::Stairs { x, y, level } => {
+                self.stairs.get(self.stairs_index((x, y, level)))
+            }
+        }
+    }
+
+    pub fn set_wall(&mut self, wall: &LevelWall, on: bool) {
+        match *wall {
+            LevelWall::Side { level, wall } => self.levels[level].set_wall(&wall, on),
+            LevelWall::Stairs { x, y, level } => {
+                let index = self.stairs_index((x, y, level));
+                self.stairs.set(index, on);
+            }
+        }
+    }
+
+    fn stairs_index(&self, (x, y, level): (usize, usize, usize)) -> usize {
+        assert!(level + 1 < self.levels(), "The top level has no stairs up");
+        self.cell_index((x, y, level))
+    }
+
+    /// Offset of the centre of `level` from the centre of the maze.
+    fn level_offset(&self, level: usize) -> Vec2 {
+        let step = self.width() as f32 + LEVEL_GAP;
+        vec2((level as f32 - (self.levels() - 1) as f32 / 2.0) * step, 0.0)
+    }
+
+    /// Chevron drawn in a cell with stairs, pointing up in the top half of
+    /// the cell for stairs up and down in the bottom half for stairs down.
+    fn stairs_marker(&self, cell: (usize, usize, usize), up: bool) -> Vec<Vec2> {
+        let center = self.cell_center(cell);
+        let (tip, side) = if up { (0.35, 0.1) } else { (-0.35, -0.1) };
+
+        vec![
+            center + vec2(-0.2, side),
+            center + vec2(0.0, tip),
+            center + vec2(0.2, side),
+        ]
+    }
+}
+
+impl Topology for LevelMaze {
+    type Cell = (usize, usize, usize);
+    type Wall = LevelWall;
+
+    fn cells(&self) -> Box<dyn Iterator<Item = (usize, usize, usize)> + '_> {
+        let cells = self.levels.iter().enumerate().flat_map(|(level, maze)| {
+            maze.cells().map(move |(x, y)| (x, y, level))
+        });
+
+        Box::new(cells)
+    }
+
+    fn cell_count(&self) -> usize {
+        self.width() * self.height() * self.levels()
+    }
+
+    fn cell_index(&self, (x, y, level): (usize, usize, usize)) -> usize {
+        (level * self.height() + y) * self.width() + x
+    }
+
+    fn neighbours(&self, cell: (usize, usize, usize)) -> Vec<((usize, usize, usize), LevelWall)> {
+        let (x, y, level) = cell;
+        let mut neighbours: Vec<_> = self.levels[level]
+            .neighbours((x, y))
+            .into_iter()
+            .map(|((nx, ny), wall)| ((nx, ny, level), LevelWall::Side { level, wall }))
+            .collect();
+
+        if level > 0 {
+            neighbours.push(((x, y, level - 1), LevelWall::Stairs { x, y, level: level - 1 }));
+        }
+        if level + 1 < self.levels() {
+            neighbours.push(((x, y, level + 1), LevelWall::Stairs { x, y, level }));
+        }
+
+        neighbours
+    }
+
+    fn walls(&self) -> Box<dyn Iterator<Item = LevelWall> + '_> {
+        let sides = self.levels.iter().enumerate().flat_map(|(level, maze)| {
+            maze.wall_iter().map(move |wall| LevelWall::Side { level, wall })
+        });
+        let stairs = self
+            .cells()
+            .filter(|&(_, _, level)| level + 1 < self.levels())
+            .map(|(x, y, level)| LevelWall::Stairs { x, y, level });
+
+        Box::new(sides.chain(stairs))
+    }
+
+    fn get_wall(&self, wall: &LevelWall) -> bool {
+        LevelMaze::get_wall(self, wall)
+    }
+
+    fn set_wall(&mut self, wall: &LevelWall, on: bool) {
+        LevelMaze::set_wall(self, wall, on)
+    }
+
+    fn size(&self) -> Vec2 {
+        let levels = self.levels() as f32;
+        vec2(levels * self.width() as f32 + (levels - 1.0) * LEVEL_GAP, self.height() as f32)
+    }
+
+    fn cell_center(&self, (x, y, level): (usize, usize, usize)) -> Vec2 {
+        self.levels[level].cell_center((x, y)) + self.level_offset(level)
+    }
+
+    fn cell_outline(&self, (x, y, level): (usize, usize, usize)) -> Vec<Vec2> {
+        let offset = self.level_offset(level);
+
+        self.levels[level]
+            .cell_outline((x, y))
+            .into_iter()
+            .map(|point| point + offset)
+            .collect()
+    }
+
+    /// Stairs are drawn as the marker in the cell they go up from.
+    fn wall_line(&self, wall: &LevelWall) -> Vec<Vec2> {
+        match *wall {
+            LevelWall::Side { level, wall } => self.levels[level]
+                .wall_line(&wall)
+                .into_iter()
+                .map(|point| point + self.level_offset(level))
+                .collect(),
+            LevelWall::Stairs { x, y, level } => self.stairs_marker((x, y, level), true),
+        }
+    }
+
+    /// Open stairs are marked at both ends, closed ones are not drawn.
+    fn wall_lines(&self, wall: &LevelWall) -> Vec<Vec<Vec2>> {
+        match *wall {
+            LevelWall::Side { .. } if self.get_wall(wall) => vec![self.wall_line(wall)],
+            LevelWall::Stairs { x, y, level } if !self.get_wall(wall) => vec![
+                self.stairs_marker((x, y, level), true),
+                self.stairs_marker((x, y, level + 1), false),
+            ],
+            _ => Vec::new(),
+        }
+    }
+
+    fn jump(&self, cell: (usize, usize, usize), wall: &LevelWall) -> Option<(Vec2, Vec2)> {
+        match *wall {
+            LevelWall::Side { .. } => None,
+            LevelWall::Stairs { x, y, level } => {
+                let lower = self.cell_center((x, y, level));
+                let upper = self.cell_center((x, y, level + 1));
+
+                Some(if cell.2 == level { (lower, upper) } else { (upper, lower) })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_maze_stairs_join_the_levels() {
+        let mut maze = LevelMaze::new(3, 3, 3, true);
+        maze.set_wall(&LevelWall::Stairs { x: 1, y: 1, level: 1 }, false);
+
+        assert_eq!(maze.neighbours((1, 1, 1)).len(), 6);
+        assert_eq!(maze.open_neighbours((1, 1, 1)), vec![(1, 1, 2)]);
+        assert_eq!(maze.open_neighbours((1, 1, 2)), vec![(1, 1, 1)]);
+    }
+
+    #[test]
+    fn level_maze_walls_are_stored_separately() {
+        let mut maze = LevelMaze::new(3, 2, 2, false);
+        let walls: Vec<LevelWall> = maze.walls().collect();
+
+        for wall in &walls {
+            maze.set_wall(wall, true);
+
+            assert_eq!(walls.iter().filter(|wall| maze.get_wall(wall)).count(), 1);
+            maze.set_wall(wall, false);
+        }
+    }
+
+    #[test]
+    fn level_maze_levels_are_side_by_side() {
+        let maze = LevelMaze::new(4, 3, 2, true);
+
+        let lower = maze.cell_center((3, 0, 0));
+        let upper = maze.cell_center((0, 0, 1));
+
+        assert_eq!(upper.x - lower.x, 1.0 + LEVEL_GAP);
+        assert_eq!(maze.size(), vec2(9.0, 3.0));
+    }
+}
@@ -0,0 +1,244 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use itertools::Itertools;
+use nannou::image;
+use nannou::text::{self, rt::point, Font, Scale};
+
+use super::bit_vec::BitVec;
+
+/// Grid of enabled and disabled cells, giving a `Maze` an arbitrary shape.
+///
+/// Generators only carve passages between enabled cells, so parts of the
+/// mask that don't touch each other, e.g. separate letters, become separate
+/// mazes that can't be solved from one to the other.
+#[derive(Clone)]
+pub struct Mask {
+    width: usize,
+    height: usize,
+    enabled: BitVec,
+}
+
+impl Mask {
+    /// Mask with all cells enabled.
+    pub fn new(width: usize, height: usize) -> Mask {
+        Mask::filled(width, height, true)
+    }
+
+    fn filled(width: usize, height: usize, enabled: bool) -> Mask {
+        Mask {
+            width,
+            height,
+            enabled: BitVec::new(width * height, enabled),
+        }
+    }
+
+    /// Mask from lines of text, one line per row. `X` marks a disabled
+    /// cell, any other character an enabled one. Short lines are padded
+    /// with disabled cells.
+    pub fn from_text(text: &str) -> Mask {
+        let lines: Vec<&str> = text.lines().collect();
+        let width = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+
+        let mut mask = Mask::new(width, lines.len());
+        for (y, line) in lines.iter().enumerate() {
+            let mut chars = line.chars();
+
+            for x in 0..width {
+                let enabled = !matches!(chars.next(), Some('X' | 'x') | None);
+                mask.set((x, y), enabled);
+            }
+        }
+
+        mask
+    }
+
+    pub fn load_text<P: AsRef<Path>>(path: P) -> io::Result<Mask> {
+        Ok(Mask::from_text(&fs::read_to_string(path)?))
+    }
+
+    /// Mask from an image with a cell per pixel. Dark pixels are disabled
+    /// cells and light pixels enabled ones.
+    pub fn from_image(image: &image::GrayImage) -> Mask {
+        let (width, height) = image.dimensions();
+        let mut mask = Mask::new(width as usize, height as usize);
+
+        for (x, y, pixel) in image.enumerate_pixels() {
+            mask.set((x as usize, y as usize), pixel.0[0] >= 128);
+        }
+
+        mask
+    }
+
+    pub fn load_png<P: AsRef<Path>>(path: P) -> image::ImageResult<Mask> {
+        let image = image::open(path)?;
+        Ok(Mask::from_image(&image.to_luma8()))
+    }
+
+    /// Mask in the shape of `text` written in the bundled font, with a
+    /// cell per pixel of a font `rows` pixels high. The font leaves room
+    /// for accents and descenders, so capitals are only about 10 cells
+    /// high at 18 rows. The mask is cropped to the letters, and letters
+    /// that don't touch are not connected. Fails if the letters don't
+    /// cover at least 2x2 cells, e.g. for blank text.
+    pub fn from_rendered_text(text: &str, rows: usize) -> Result<Mask, String> {
+        Mask::from_rendered_text_with_font(text, rows, &text::font::default_notosans())
+    }
+
+    pub fn from_rendered_text_with_font(
+        text: &str,
+        rows: usize,
+        font: &Font,
+    ) -> Result<Mask, String> {
+        let unit_metrics = font.v_metrics(Scale::uniform(1.0));
+        let scale = Scale::uniform(rows as f32 / (unit_metrics.ascent - unit_metrics.descent));
+        let baseline = font.v_metrics(scale).ascent;
+
+        let glyphs: Vec<_> = font.layout(text, scale, point(0.0, baseline)).collect();
+        let width = glyphs
+            .iter()
+            .filter_map(|glyph| glyph.pixel_bounding_box())
+            .map(|bounds| bounds.max.x.max(0) as usize)
+            .max()
+            .unwrap_or(0);
+
+        let mut mask = Mask::filled(width, rows, false);
+        for glyph in &glyphs {
+            let bounds = match glyph.pixel_bounding_box() {
+                Some(bounds) => bounds,
+                None => continue,
+            };
+
+            glyph.draw(|x, y, coverage| {
+                let x = bounds.min.x + x as i32;
+                let y = bounds.min.y + y as i32;
+                let inside = (0..width as i32).contains(&x) && (0..rows as i32).contains(&y);
+
+                // Include partly covered pixels to keep thin strokes connected
+                if inside && coverage >= 0.2 {
+                    mask.set((x as usize, y as usize), true);
+                }
+            });
+        }
+
+        let mask = mask.cropped();
+        if mask.width() < 2 || mask.height() < 2 {
+            return Err(format!("Text {:?} is too small to fit a maze at {} rows", text, rows));
+        }
+
+        Ok(mask)
+    }
+
+    /// Smallest part of the mask that contains all enabled cells.
+    fn cropped(&self) -> Mask {
+        let enabled: Vec<(usize, usize)> = itertools::iproduct!(0..self.width, 0..self.height)
+            .filter(|&cell| self.get(cell))
+            .collect();
+
+        let (min_x, max_x) = match enabled.iter().map(|&(x, _)| x).minmax().into_option() {
+            Some(range) => range,
+            None => return Mask::filled(0, 0, false),
+        };
+        let (min_y, max_y) = enabled.iter().map(|&(_, y)| y).minmax().into_option().unwrap();
+
+        let mut mask = Mask::filled(max_x - min_x + 1, max_y - min_y + 1, false);
+        for (x, y) in enabled {
+            mask.set((x - min_x, y - min_y), true);
+        }
+
+        mask
+    }
+
+    pub fn width(&self) -> usize { self.width }
+
+    pub fn height(&self) -> usize { self.height }
+
+    pub fn get(&self, (x, y): (usize, usize)) -> bool {
+        assert!(x < self.width && y < self.height);
+        self.enabled.get(y * self.width + x)
+    }
+
+    pub fn set(&mut self, (x, y): (usize, usize), enabled: bool) {
+        assert!(x < self.width && y < self.height);
+        self.enabled.set(y * self.width + x, enabled);
+    }
+
+    pub fn enabled_count(&self) -> usize {
+        itertools::iproduct!(0..self.width, 0..self.height)
+            .filter(|&cell| self.get(cell))
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mask_from_text_disables_x_cells() {
+        let mask = Mask::from_text("..X\nX..\n");
+
+        assert_eq!((mask.width(), mask.height()), (3, 2));
+        assert!(!mask.get((2, 0)));
+        assert!(!mask.get((0, 1)));
+        assert_eq!(mask.enabled_count(), 4);
+    }
+
+    #[test]
+    fn mask_from_text_pads_short_lines() {
+        let mask = Mask::from_text("...\n.\n");
+
+        assert_eq!(mask.width(), 3);
+        assert!(!mask.get((1, 1)));
+        assert!(!mask.get((2, 1)));
+    }
+
+    #[test]
+    fn mask_from_image_disables_dark_pixels() {
+        let image = image::GrayImage::from_fn(3, 2, |x, _| image::Luma([if x == 1 { 0 } else { 255 }]));
+        let mask = Mask::from_image(&image);
+
+        assert_eq!((mask.width(), mask.height()), (3, 2));
+        assert!(!mask.get((1, 0)));
+        assert!(mask.get((2, 1)));
+        assert_eq!(mask.enabled_count(), 4);
+    }
+
+    #[test]
+    fn mask_can_be_loaded_from_png() {
+        let path = std::env::temp_dir().join("maze_mask_can_be_loaded_from_png.png");
+        let image = image::GrayImage::from_fn(4, 4, |x, y| image::Luma([if x == y { 0 } else { 255 }]));
+        image.save(&path).unwrap();
+
+        let mask = Mask::load_png(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(mask.enabled_count(), 12);
+    }
+
+    #[test]
+    fn mask_from_rendered_text_fits_in_the_requested_height() {
+        let mask = Mask::from_rendered_text("Maze", 16).unwrap();
+
+        assert!(mask.height() <= 16);
+        assert!(mask.enabled_count() > 0);
+        assert!(mask.enabled_count() < mask.width() * mask.height());
+    }
+
+    #[test]
+    fn mask_from_rendered_text_grows_with_the_text() {
+        let short = Mask::from_rendered_text("M", 16).unwrap();
+        let long = Mask::from_rendered_text("MM", 16).unwrap();
+
+        assert!(long.width() > short.width());
+        assert!(long.enabled_count() > short.enabled_count());
+    }
+
+    #[test]
+    fn mask_from_rendered_text_rejects_blank_text() {
+        assert!(Mask::from_rendered_text("", 16).is_err());
+        assert!(Mask::from_rendered_text(" ", 16).is_err());
+        assert!(Mask::from_rendered_text("Maze", 0).is_err());
+    }
+}
@@ -1,11 +1,19 @@
-use super::{WallIterator, Wall};
+use nannou::prelude::*;
+
+use super::bit_vec::BitVec;
+use super::{Mask, Topology, WallIterator, Wall, Wrap};
 use super::{wall::Direction::*};
 
+#[derive(Clone)]
 pub struct Maze {
     width: usize,
     height: usize,
-    vertical_walls: Vec<Vec<bool>>,
-    horizontal_walls: Vec<Vec<bool>>,
+    // Vertical walls (width + 1 columns of height) followed by horizontal
+    // walls (width columns of height + 1). Walls on joined edges are
+    // stored in the first column or row.
+    walls: BitVec,
+    mask: Option<Mask>,
+    wrap: Wrap,
 }
 
 impl Maze {
@@ -13,31 +21,75 @@ impl Maze {
         assert!(width > 1);
         assert!(height > 1);
 
-        let vertical_walls = vec![vec![walls_on; height]; width + 1];
-        let horizontal_walls = vec![vec![walls_on; height + 1]; width];
+        let wall_count = (width + 1) * height + width * (height + 1);
 
         Maze {
             width,
             height,
-            vertical_walls,
-            horizontal_walls,
+            walls: BitVec::new(wall_count, walls_on),
+            mask: None,
+            wrap: Wrap::None,
         }
     }
 
-    pub fn new_with_edges(width: usize, height: usize, walls_on: bool) -> Maze {
-        let mut maze = Maze::new(width, height, walls_on);
+    /// Maze whose edges are joined to the opposite ones as given by `wrap`,
+    /// so passages can leave on one side and come back on the other. Fails
+    /// if a wrapped side is under 3 cells long.
+    pub fn with_wrap(
+        width: usize,
+        height: usize,
+        wrap: Wrap,
+        walls_on: bool,
+    ) -> Result<Maze, String> {
+        // Narrower mazes would have cells that are neighbours twice
+        if (wrap.horizontal() && width < 3) || (wrap.vertical() && height < 3) {
+            let size = format!("{}x{}", width, height);
+            return Err(format!("Wrapped mazes must be at least 3 cells across, not {}", size));
+        }
 
-        for i in maze.vertical_walls.first_mut().unwrap() {
-            *i = true;
+        Ok(Maze {
+            wrap,
+            ..Maze::new(width, height, walls_on)
+        })
+    }
+
+    /// Maze of the size of `mask` that only has its enabled cells. Walls
+    /// between enabled and disabled cells are always on. Fails if the mask
+    /// is under 2x2 cells or has no enabled cell.
+    pub fn with_mask(mask: Mask, walls_on: bool) -> Result<Maze, String> {
+        if mask.width() < 2 || mask.height() < 2 {
+            let size = format!("{}x{}", mask.width(), mask.height());
+            return Err(format!("Masks must be at least 2x2 cells, not {}", size));
+        }
+        if mask.enabled_count() == 0 {
+            return Err("Masks must have at least one enabled cell".to_string());
         }
 
-        for i in maze.vertical_walls.last_mut().unwrap() {
-            *i = true;
+        let mut maze = Maze::new_with_edges(mask.width(), mask.height(), walls_on);
+
+        for cell in itertools::iproduct!(0..mask.width(), 0..mask.height()) {
+            for (neighbour, wall) in maze.neighbours(cell) {
+                if mask.get(cell) != mask.get(neighbour) {
+                    maze.set_wall(&wall, true);
+                }
+            }
         }
 
-        for row in &mut maze.horizontal_walls {
-            *row.first_mut().unwrap() = true;
-            *row.last_mut().unwrap() = true;
+        maze.mask = Some(mask);
+        Ok(maze)
+    }
+
+    pub fn new_with_edges(width: usize, height: usize, walls_on: bool) -> Maze {
+        let mut maze = Maze::new(width, height, walls_on);
+
+        for y in 0..height {
+            maze.set_wall(&Wall { x: 0, y, dir: Left }, true);
+            maze.set_wall(&Wall { x: width - 1, y, dir: Right }, true);
+        }
+
+        for x in 0..width {
+            maze.set_wall(&Wall { x, y: 0, dir: Up }, true);
+            maze.set_wall(&Wall { x, y: height - 1, dir: Down }, true);
         }
 
         maze
@@ -47,25 +99,172 @@ impl Maze {
 
     pub fn height(&self) -> usize { self.height }
 
-    pub fn wall_iter(&self) -> WallIterator {
+    pub fn wrap(&self) -> Wrap { self.wrap }
+
+    pub fn is_enabled(&self, cell: (usize, usize)) -> bool {
+        self.mask.as_ref().is_none_or(|mask| mask.get(cell))
+    }
+
+    pub fn wall_iter(&self) -> WallIterator<'_> {
         WallIterator::new(self)
     }
 
+    /// Number of bytes allocated on the heap for the wall states.
+    pub fn wall_storage_size(&self) -> usize {
+        self.walls.heap_size()
+    }
+
+    #[inline]
     pub fn set_wall(&mut self, wall: &Wall, on: bool) {
-        match wall {
-            &Wall { x, y, dir: Up } => self.horizontal_walls[x][y] = on,
-            &Wall { x, y, dir: Down } => self.horizontal_walls[x][y+1] = on,
-            &Wall { x, y, dir: Left } => self.vertical_walls[x][y] = on,
-            &Wall { x, y, dir: Right } => self.vertical_walls[x+1][y] = on,
-        }
+        let index = self.wall_index(wall);
+        self.walls.set(index, on);
     }
 
+    #[inline]
     pub fn get_wall(&self, wall: &Wall) -> bool {
-        match wall {
-            &Wall { x, y, dir: Up } => self.horizontal_walls[x][y],
-            &Wall { x, y, dir: Down } => self.horizontal_walls[x][y+1],
-            &Wall { x, y, dir: Left } => self.vertical_walls[x][y],
-            &Wall { x, y, dir: Right } => self.vertical_walls[x+1][y],
+        self.walls.get(self.wall_index(wall))
+    }
+
+    #[inline]
+    fn wall_index(&self, wall: &Wall) -> usize {
+        let vertical = |x: usize, y: usize| {
+            assert!(x <= self.width && y < self.height);
+            let x = if self.wrap.horizontal() { x % self.width } else { x };
+            x * self.height + y
+        };
+        let horizontal = |x: usize, y: usize| {
+            assert!(x < self.width && y <= self.height);
+            let y = if self.wrap.vertical() { y % self.height } else { y };
+            (self.width + 1) * self.height + x * (self.height + 1) + y
+        };
+
+        match *wall {
+            Wall { x, y, dir: Up } => horizontal(x, y),
+            Wall { x, y, dir: Down } => horizontal(x, y + 1),
+            Wall { x, y, dir: Left } => vertical(x, y),
+            Wall { x, y, dir: Right } => vertical(x + 1, y),
         }
     }
-}
\ No newline at end of file
+
+    /// Cell on the other side of `wall` from the cell it belongs to, if
+    /// the wall is not on an outer edge.
+    fn cell_across(&self, wall: &Wall) -> Option<(usize, usize)> {
+        let Wall { x, y, dir } = *wall;
+        let (last_x, last_y) = (self.width - 1, self.height - 1);
+
+        match dir {
+            Up if y > 0 => Some((x, y - 1)),
+            Up if self.wrap.vertical() => Some((x, last_y)),
+            Down if y < last_y => Some((x, y + 1)),
+            Down if self.wrap.vertical() => Some((x, 0)),
+            Left if x > 0 => Some((x - 1, y)),
+            Left if self.wrap.horizontal() => Some((last_x, y)),
+            Right if x < last_x => Some((x + 1, y)),
+            Right if self.wrap.horizontal() => Some((0, y)),
+            _ => None,
+        }
+    }
+
+    /// Whether `wall` is on an edge joined to the opposite one.
+    fn is_wrapped(&self, wall: &Wall) -> bool {
+        let Wall { x, y, dir } = *wall;
+
+        match dir {
+            Up => y == 0 && self.wrap.vertical(),
+            Down => y == self.height - 1 && self.wrap.vertical(),
+            Left => x == 0 && self.wrap.horizontal(),
+            Right => x == self.width - 1 && self.wrap.horizontal(),
+        }
+    }
+}
+
+impl Topology for Maze {
+    type Cell = (usize, usize);
+    type Wall = Wall;
+
+    fn cells(&self) -> Box<dyn Iterator<Item = (usize, usize)> + '_> {
+        let cells = itertools::iproduct!(0..self.height, 0..self.width)
+            .map(|(y, x)| (x, y))
+            .filter(|&cell| self.is_enabled(cell));
+
+        Box::new(cells)
+    }
+
+    fn cell_count(&self) -> usize {
+        self.width * self.height
+    }
+
+    fn cell_index(&self, (x, y): (usize, usize)) -> usize {
+        y * self.width + x
+    }
+
+    fn neighbours(&self, (x, y): (usize, usize)) -> Vec<((usize, usize), Wall)> {
+        [Up, Down, Left, Right]
+            .into_iter()
+            .map(|dir| Wall { x, y, dir })
+            .filter_map(|wall| self.cell_across(&wall).map(|neighbour| (neighbour, wall)))
+            .filter(|&(neighbour, _)| self.is_enabled(neighbour))
+            .collect()
+    }
+
+    fn walls(&self) -> Box<dyn Iterator<Item = Wall> + '_> {
+        if self.mask.is_none() {
+            return Box::new(self.wall_iter());
+        }
+
+        // Skip the walls that only touch disabled cells
+        let walls = self.wall_iter().filter(|wall| {
+            self.is_enabled((wall.x, wall.y))
+                || self.cell_across(wall).is_some_and(|other| self.is_enabled(other))
+        });
+
+        Box::new(walls)
+    }
+
+    fn get_wall(&self, wall: &Wall) -> bool {
+        Maze::get_wall(self, wall)
+    }
+
+    fn set_wall(&mut self, wall: &Wall, on: bool) {
+        Maze::set_wall(self, wall, on)
+    }
+
+    fn size(&self) -> Vec2 {
+        vec2(self.width as f32, self.height as f32)
+    }
+
+    fn cell_center(&self, (x, y): (usize, usize)) -> Vec2 {
+        let top_left = vec2(-(self.width as f32) / 2.0, self.height as f32 / 2.0);
+        top_left + vec2(x as f32 + 0.5, -(y as f32) - 0.5)
+    }
+
+    fn cell_outline(&self, cell: (usize, usize)) -> Vec<Vec2> {
+        let center = self.cell_center(cell);
+
+        [(-0.5, 0.5), (0.5, 0.5), (0.5, -0.5), (-0.5, -0.5)]
+            .into_iter()
+            .map(|(x, y)| center + vec2(x, y))
+            .collect()
+    }
+
+    fn wall_line(&self, wall: &Wall) -> Vec<Vec2> {
+        let corners = self.cell_outline((wall.x, wall.y));
+        let (start, end) = match wall.dir {
+            Up => (corners[0], corners[1]),
+            Right => (corners[1], corners[2]),
+            Down => (corners[3], corners[2]),
+            Left => (corners[0], corners[3]),
+        };
+
+        vec![start, end]
+    }
+
+    fn wrapped_wall_line(&self, wall: &Wall) -> Option<Vec<Vec2>> {
+        if !self.is_wrapped(wall) {
+            return None;
+        }
+
+        let (x, y) = self.cell_across(wall)?;
+        Some(self.wall_line(&Wall { x, y, dir: wall.dir.opposite() }))
+    }
+}
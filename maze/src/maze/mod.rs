@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 pub mod maze;
 pub use crate::maze::maze::Maze;
 
@@ -7,6 +8,32 @@ pub use crate::maze::wall::Wall;
 pub mod iterator;
 pub use crate::maze::iterator::WallIterator;
 
+pub mod wrap;
+pub use crate::maze::wrap::Wrap;
+
+pub mod mask;
+pub use crate::maze::mask::Mask;
+
+pub mod topology;
+pub use crate::maze::topology::Topology;
+
+pub mod hex;
+pub use crate::maze::hex::{HexMaze, HexWall};
+
+pub mod polar;
+pub use crate::maze::polar::{PolarMaze, PolarWall};
+
+pub mod delta;
+pub use crate::maze::delta::{DeltaMaze, DeltaWall};
+
+pub mod weave;
+pub use crate::maze::weave::{Axis, WeaveMaze, WeaveWall};
+
+pub mod level;
+pub use crate::maze::level::{LevelMaze, LevelWall};
+
+mod bit_vec;
+
 #[cfg(test)]
 mod tests {
     use crate::maze::wall::Direction::*;
@@ -23,13 +50,13 @@ mod tests {
     #[test]
     fn maze_get_wall_returns_false_when_maze_constructed_with_walls_off() {
         let maze = Maze::new(2, 2, false);
-        assert_eq!(maze.get_wall(&Wall {x:0, y:0, dir: Right}), false);
+        assert!(!maze.get_wall(&Wall {x:0, y:0, dir: Right}));
     }
 
     #[test]
     fn maze_get_wall_returns_true_when_maze_constructed_with_walls_on() {
         let maze = Maze::new(2, 2, true);
-        assert_eq!(maze.get_wall(&Wall {x:0, y:0, dir: Right}), true);
+        assert!(maze.get_wall(&Wall {x:0, y:0, dir: Right}));
     }
 
     #[test]
@@ -40,7 +67,7 @@ mod tests {
         maze.set_wall(&wall, true);
         let maze = maze;
 
-        assert_eq!(maze.get_wall(&wall), true);
+        assert!(maze.get_wall(&wall));
     }
 
     #[test]
@@ -95,4 +122,128 @@ mod tests {
 
         assert_eq!(on_count, 40);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn maze_adjacent_cells_share_walls() {
+        let mut maze = Maze::new(3, 3, false);
+
+        maze.set_wall(&Wall { x: 0, y: 1, dir: Right }, true);
+        maze.set_wall(&Wall { x: 2, y: 1, dir: Up }, true);
+
+        assert!(maze.get_wall(&Wall { x: 1, y: 1, dir: Left }));
+        assert!(maze.get_wall(&Wall { x: 2, y: 0, dir: Down }));
+    }
+
+    #[test]
+    fn maze_stores_walls_in_one_bit_each() {
+        let maze = Maze::new(1000, 1000, true);
+        let wall_count = 1001 * 1000 * 2;
+
+        assert!(maze.wall_storage_size() * 8 < wall_count + 64);
+    }
+
+    #[test]
+    fn maze_cell_index_follows_cells_order() {
+        let maze = Maze::new(4, 3, true);
+
+        for (index, cell) in maze.cells().enumerate() {
+            assert_eq!(maze.cell_index(cell), index);
+        }
+        assert_eq!(maze.cell_count(), 12);
+    }
+
+    #[test]
+    fn maze_neighbours_stay_inside_the_maze() {
+        let maze = Maze::new(3, 3, true);
+
+        assert_eq!(maze.neighbours((0, 0)).len(), 2);
+        assert_eq!(maze.neighbours((1, 0)).len(), 3);
+        assert_eq!(maze.neighbours((1, 1)).len(), 4);
+    }
+
+    #[test]
+    fn maze_open_neighbours_skip_walls() {
+        let mut maze = Maze::new(3, 3, true);
+        maze.set_wall(&Wall { x: 1, y: 1, dir: Up }, false);
+
+        assert_eq!(maze.open_neighbours((1, 1)), vec![(1, 0)]);
+    }
+
+    #[test]
+    fn maze_wall_line_is_between_the_cell_centers() {
+        let maze = Maze::new(2, 2, true);
+        let line = maze.wall_line(&Wall { x: 0, y: 0, dir: Right });
+
+        let middle = (line[0] + line[1]) / 2.0;
+        let centers = (maze.cell_center((0, 0)) + maze.cell_center((1, 0))) / 2.0;
+
+        assert_eq!(middle, centers);
+        assert_eq!(line[0].distance(line[1]), 1.0);
+    }
+
+    #[test]
+    fn masked_maze_skips_disabled_cells() {
+        let maze = Maze::with_mask(Mask::from_text("X..\n...\n..X\n"), true).unwrap();
+
+        assert_eq!(maze.cells().count(), 7);
+        assert!(!maze.cells().any(|cell| cell == (0, 0) || cell == (2, 2)));
+        assert_eq!(maze.neighbours((1, 0)).len(), 2);
+    }
+
+    #[test]
+    fn masked_maze_rejects_masks_it_cant_fit_in() {
+        assert!(Maze::with_mask(Mask::from_text("...\n"), true).is_err());
+        assert!(Maze::with_mask(Mask::from_text("XX\nXX\n"), true).is_err());
+        assert!(Maze::with_mask(Mask::new(0, 0), true).is_err());
+    }
+
+    #[test]
+    fn masked_maze_skips_walls_between_disabled_cells() {
+        let maze = Maze::with_mask(Mask::from_text("XX.\nXX.\n"), false).unwrap();
+
+        // Only the walls around the enabled column are left, and they are on
+        let walls: Vec<Wall> = maze.walls().collect();
+
+        assert_eq!(walls.len(), 7);
+        assert_eq!(walls.iter().filter(|wall| maze.get_wall(wall)).count(), 6);
+    }
+
+    #[test]
+    fn cylinder_maze_joins_left_and_right_edges() {
+        let mut maze = Maze::with_wrap(3, 3, Wrap::Cylinder, true).unwrap();
+        maze.set_wall(&Wall { x: 0, y: 1, dir: Left }, false);
+
+        assert!(!maze.get_wall(&Wall { x: 2, y: 1, dir: Right }));
+        assert_eq!(maze.open_neighbours((2, 1)), vec![(0, 1)]);
+        assert_eq!(maze.neighbours((0, 0)).len(), 3);
+    }
+
+    #[test]
+    fn wrapping_maze_rejects_sides_too_short_to_wrap() {
+        assert!(Maze::with_wrap(2, 4, Wrap::Cylinder, true).is_err());
+        assert!(Maze::with_wrap(4, 2, Wrap::Cylinder, true).is_ok());
+        assert!(Maze::with_wrap(4, 2, Wrap::Torus, true).is_err());
+    }
+
+    #[test]
+    fn wrapping_maze_lists_joined_walls_once() {
+        let cylinder = Maze::with_wrap(4, 3, Wrap::Cylinder, true).unwrap();
+        let torus = Maze::with_wrap(4, 3, Wrap::Torus, true).unwrap();
+
+        assert_eq!(cylinder.walls().count(), 2 * 4 * 3 + 4);
+        assert_eq!(torus.walls().count(), 2 * 4 * 3);
+    }
+
+    #[test]
+    fn wrapped_wall_line_is_on_the_opposite_edge() {
+        let maze = Maze::with_wrap(3, 3, Wrap::Torus, true).unwrap();
+        let wall = Wall { x: 1, y: 0, dir: Up };
+
+        let line = maze.wall_line(&wall);
+        let wrapped = maze.wrapped_wall_line(&wall).unwrap();
+
+        assert_eq!(line[0].y, 1.5);
+        assert_eq!(wrapped[0].y, -1.5);
+        assert!(maze.wrapped_wall_line(&Wall { x: 1, y: 1, dir: Up }).is_none());
+    }
+}
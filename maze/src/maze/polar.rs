@@ -0,0 +1,292 @@
+use std::f32::consts::TAU;
+
+use nannou::prelude::*;
+
+use super::bit_vec::BitVec;
+use super::Topology;
+
+use PolarSide::*;
+
+/// Side of a polar cell. Clockwise and counter-clockwise sides are the
+/// radial walls, inward and outward sides are the arcs.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PolarSide { Inward, Outward, Clockwise, CounterClockwise }
+
+/// Wall of the cell `index` in `ring`. Outward walls exist only on the
+/// outermost ring; between two rings, use the inward wall of the outer cell.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PolarWall {
+    pub ring: usize,
+    pub index: usize,
+    pub side: PolarSide,
+}
+
+/// Circular maze of rings around a single centre cell. Rings are split into
+/// more cells as they get longer, so that the cells stay roughly square.
+/// Cells are `(ring, index)`, with indices going counter-clockwise from the
+/// east.
+#[derive(Clone)]
+pub struct PolarMaze {
+    ring_sizes: Vec<usize>,
+    ring_offsets: Vec<usize>,
+    // Inward and counter-clockwise wall of every cell, followed by the
+    // outward walls of the outermost ring
+    walls: BitVec,
+}
+
+impl PolarMaze {
+    pub fn new(rings: usize, walls_on: bool) -> PolarMaze {
+        assert!(rings > 1);
+
+        let mut ring_sizes = vec![1];
+        for ring in 1..rings {
+            let previous = ring_sizes[ring - 1];
+            // Each cell is about as wide as the ring is thick
+            let cell_width = TAU * ring as f32 / previous as f32;
+            ring_sizes.push(previous * cell_width.round().max(1.0) as usize);
+        }
+
+        let ring_offsets: Vec<usize> = ring_sizes
+            .iter()
+            .scan(0, |offset, size| {
+                let ring_offset = *offset;
+                *offset += size;
+                Some(ring_offset)
+            })
+            .collect();
+
+        let cell_count: usize = ring_sizes.iter().sum();
+        let wall_count = cell_count * 2 + ring_sizes[rings - 1];
+
+        PolarMaze {
+            ring_sizes,
+            ring_offsets,
+            walls: BitVec::new(wall_count, walls_on),
+        }
+    }
+
+    pub fn rings(&self) -> usize { self.ring_sizes.len() }
+
+    pub fn ring_size(&self, ring: usize) -> usize { self.ring_sizes[ring] }
+
+    pub fn get_wall(&self, wall: &PolarWall) -> bool {
+        self.walls.get(self.wall_index(wall))
+    }
+
+    pub fn set_wall(&mut self, wall: &PolarWall, on: bool) {
+        let index = self.wall_index(wall);
+        self.walls.set(index, on);
+    }
+
+    /// Number of cells in `ring` for each cell in the ring inside of it.
+    fn ratio(&self, ring: usize) -> usize {
+        self.ring_sizes[ring] / self.ring_sizes[ring - 1]
+    }
+
+    fn wall_index(&self, wall: &PolarWall) -> usize {
+        let &PolarWall { ring, index, side } = wall;
+        let size = self.ring_sizes[ring];
+        assert!(index < size);
+
+        let cell_index = |index: usize| (self.ring_offsets[ring] + index) * 2;
+
+        match side {
+            Inward => {
+                assert!(ring > 0, "The centre cell has no inward wall");
+                cell_index(index)
+            }
+            CounterClockwise => cell_index(index) + 1,
+            Clockwise => cell_index((index + size - 1) % size) + 1,
+            Outward => {
+                assert!(ring == self.rings() - 1, "Only the outermost ring has outward walls");
+                self.cell_count() * 2 + index
+            }
+        }
+    }
+
+    /// Angles at which the cell starts and ends.
+    fn cell_angles(&self, (ring, index): (usize, usize)) -> (f32, f32) {
+        let step = TAU / self.ring_sizes[ring] as f32;
+        (index as f32 * step, (index + 1) as f32 * step)
+    }
+
+    fn arc(radius: f32, start: f32, end: f32) -> Vec<Vec2> {
+        let segments = ((end - start) * radius * 4.0).ceil().max(1.0) as usize;
+
+        (0..=segments)
+            .map(|segment| start + (end - start) * segment as f32 / segments as f32)
+            .map(|angle| PolarMaze::point(radius, angle))
+            .collect()
+    }
+
+    fn point(radius: f32, angle: f32) -> Vec2 {
+        vec2(angle.cos(), angle.sin()) * radius
+    }
+}
+
+impl Topology for PolarMaze {
+    type Cell = (usize, usize);
+    type Wall = PolarWall;
+
+    fn cells(&self) -> Box<dyn Iterator<Item = (usize, usize)> + '_> {
+        Box::new(
+            self.ring_sizes
+                .iter()
+                .enumerate()
+                .flat_map(|(ring, &size)| (0..size).map(move |index| (ring, index))),
+        )
+    }
+
+    fn cell_count(&self) -> usize {
+        self.ring_sizes.iter().sum()
+    }
+
+    fn cell_index(&self, (ring, index): (usize, usize)) -> usize {
+        self.ring_offsets[ring] + index
+    }
+
+    fn neighbours(&self, (ring, index): (usize, usize)) -> Vec<((usize, usize), PolarWall)> {
+        let mut neighbours = Vec::new();
+        let wall = |side| PolarWall { ring, index, side };
+
+        if ring > 0 {
+            let size = self.ring_sizes[ring];
+
+            neighbours.push(((ring - 1, index / self.ratio(ring)), wall(Inward)));
+            neighbours.push(((ring, (index + size - 1) % size), wall(Clockwise)));
+            neighbours.push(((ring, (index + 1) % size), wall(CounterClockwise)));
+        }
+
+        if ring + 1 < self.rings() {
+            let ratio = self.ratio(ring + 1);
+
+            for outer in index * ratio..(index + 1) * ratio {
+                let outer_wall = PolarWall { ring: ring + 1, index: outer, side: Inward };
+                neighbours.push(((ring + 1, outer), outer_wall));
+            }
+        }
+
+        neighbours
+    }
+
+    fn walls(&self) -> Box<dyn Iterator<Item = PolarWall> + '_> {
+        let outermost = self.rings() - 1;
+
+        let walls = self.cells().filter(|&(ring, _)| ring > 0).flat_map(move |(ring, index)| {
+            let outward = (ring == outermost).then_some(Outward);
+
+            [Inward, CounterClockwise]
+                .into_iter()
+                .chain(outward)
+                .map(move |side| PolarWall { ring, index, side })
+        });
+
+        Box::new(walls)
+    }
+
+    fn get_wall(&self, wall: &PolarWall) -> bool {
+        PolarMaze::get_wall(self, wall)
+    }
+
+    fn set_wall(&mut self, wall: &PolarWall, on: bool) {
+        PolarMaze::set_wall(self, wall, on)
+    }
+
+    fn size(&self) -> Vec2 {
+        Vec2::splat(self.rings() as f32 * 2.0)
+    }
+
+    fn cell_center(&self, cell: (usize, usize)) -> Vec2 {
+        if cell.0 == 0 {
+            return Vec2::ZERO;
+        }
+
+        let (start, end) = self.cell_angles(cell);
+        PolarMaze::point(cell.0 as f32 + 0.5, (start + end) / 2.0)
+    }
+
+    fn cell_outline(&self, cell: (usize, usize)) -> Vec<Vec2> {
+        let (ring, _) = cell;
+        let (start, end) = self.cell_angles(cell);
+
+        if ring == 0 {
+            return PolarMaze::arc(1.0, 0.0, TAU);
+        }
+
+        let mut outline = PolarMaze::arc(ring as f32, start, end);
+        outline.extend(PolarMaze::arc(ring as f32 + 1.0, start, end).into_iter().rev());
+        outline
+    }
+
+    fn wall_line(&self, wall: &PolarWall) -> Vec<Vec2> {
+        let &PolarWall { ring, index, side } = wall;
+        let (start, end) = self.cell_angles((ring, index));
+        let radius = ring as f32;
+
+        match side {
+            Inward => PolarMaze::arc(radius, start, end),
+            Outward => PolarMaze::arc(radius + 1.0, start, end),
+            Clockwise => vec![
+                PolarMaze::point(radius, start),
+                PolarMaze::point(radius + 1.0, start),
+            ],
+            CounterClockwise => vec![
+                PolarMaze::point(radius, end),
+                PolarMaze::point(radius + 1.0, end),
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polar_maze_rings_are_subdivided() {
+        let maze = PolarMaze::new(5, true);
+        let sizes: Vec<usize> = (0..5).map(|ring| maze.ring_size(ring)).collect();
+
+        assert_eq!(sizes, vec![1, 6, 12, 24, 24]);
+    }
+
+    #[test]
+    fn polar_maze_neighbours_are_mutual() {
+        let maze = PolarMaze::new(5, true);
+
+        for cell in maze.cells() {
+            for (neighbour, _) in maze.neighbours(cell) {
+                let back = maze.neighbours(neighbour);
+                assert!(back.iter().any(|&(other, _)| other == cell));
+            }
+        }
+    }
+
+    #[test]
+    fn polar_maze_walls_are_stored_separately() {
+        let mut maze = PolarMaze::new(4, false);
+        let walls: Vec<PolarWall> = maze.walls().collect();
+
+        for wall in &walls {
+            maze.set_wall(wall, true);
+
+            assert_eq!(walls.iter().filter(|wall| maze.get_wall(wall)).count(), 1);
+            maze.set_wall(wall, false);
+        }
+    }
+
+    #[test]
+    fn polar_maze_neighbour_walls_are_listed() {
+        let mut maze = PolarMaze::new(4, true);
+
+        for cell in maze.cells().collect::<Vec<_>>() {
+            for (neighbour, wall) in maze.neighbours(cell) {
+                maze.set_wall(&wall, false);
+
+                assert!(maze.open_neighbours(neighbour).contains(&cell));
+                assert!(maze.walls().any(|listed| !maze.get_wall(&listed)));
+                maze.set_wall(&wall, true);
+            }
+        }
+    }
+}
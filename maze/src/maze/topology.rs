@@ -0,0 +1,88 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::iter;
+
+use nannou::prelude::Vec2;
+
+use crate::draw::line_middle;
+
+/// Grid of cells separated by walls.
+///
+/// Generators, the solver and the drawing code only go through this trait,
+/// so they work on any kind of grid. Geometry is expressed in cell units,
+/// e.g. a square cell is 1 x 1, with the origin in the middle of the maze
+/// and y pointing up.
+pub trait Topology: Clone {
+    type Cell: Copy + Eq + Hash + Debug;
+    type Wall: Copy + Debug;
+
+    /// Every cell of the maze, in increasing `cell_index` order.
+    fn cells(&self) -> Box<dyn Iterator<Item = Self::Cell> + '_>;
+
+    /// Upper bound of `cell_index`.
+    fn cell_count(&self) -> usize;
+
+    /// Unique index of `cell` below `cell_count()`, for storing per cell
+    /// data in a `Vec`.
+    fn cell_index(&self, cell: Self::Cell) -> usize;
+
+    /// Cells adjacent to `cell` and the walls shared with them, whether the
+    /// walls are on or off.
+    fn neighbours(&self, cell: Self::Cell) -> Vec<(Self::Cell, Self::Wall)>;
+
+    /// Every wall of the maze, including the outer edges, each exactly once.
+    fn walls(&self) -> Box<dyn Iterator<Item = Self::Wall> + '_>;
+
+    fn get_wall(&self, wall: &Self::Wall) -> bool;
+
+    fn set_wall(&mut self, wall: &Self::Wall, on: bool);
+
+    /// Width and height of the box containing the whole maze.
+    fn size(&self) -> Vec2;
+
+    fn cell_center(&self, cell: Self::Cell) -> Vec2;
+
+    /// Corners of the cell, in order around it.
+    fn cell_outline(&self, cell: Self::Cell) -> Vec<Vec2>;
+
+    /// Points of the line drawn for `wall`, on the side of the cell the
+    /// wall belongs to.
+    fn wall_line(&self, wall: &Self::Wall) -> Vec<Vec2>;
+
+    /// Line of `wall` on the other side of the maze, for walls between
+    /// cells on opposite edges of a maze that wraps around.
+    fn wrapped_wall_line(&self, _wall: &Self::Wall) -> Option<Vec<Vec2>> {
+        None
+    }
+
+    /// Points where a path going through `wall` from `cell` leaves the
+    /// drawing and comes back, for passages between cells that are not
+    /// drawn next to each other. By default the middles of both lines of a
+    /// wrapped wall.
+    fn jump(&self, _cell: Self::Cell, wall: &Self::Wall) -> Option<(Vec2, Vec2)> {
+        let entry = self.wrapped_wall_line(wall)?;
+
+        Some((line_middle(&self.wall_line(wall)), line_middle(&entry)))
+    }
+
+    /// Every line drawn for `wall` in its current state. By default the
+    /// lines of the wall when it is on, and nothing when it is off.
+    fn wall_lines(&self, wall: &Self::Wall) -> Vec<Vec<Vec2>> {
+        if !self.get_wall(wall) {
+            return Vec::new();
+        }
+
+        iter::once(self.wall_line(wall))
+            .chain(self.wrapped_wall_line(wall))
+            .collect()
+    }
+
+    /// Cells that can be reached from `cell` in one step.
+    fn open_neighbours(&self, cell: Self::Cell) -> Vec<Self::Cell> {
+        self.neighbours(cell)
+            .into_iter()
+            .filter(|(_, wall)| !self.get_wall(wall))
+            .map(|(neighbour, _)| neighbour)
+            .collect()
+    }
+}
@@ -0,0 +1,351 @@
+use nannou::prelude::*;
+
+use super::bit_vec::BitVec;
+use super::wall::Direction::{self, *};
+use super::{Maze, Topology, Wall};
+
+/// Direction of a passage through a cell.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Axis { Horizontal, Vertical }
+
+impl Axis {
+    fn of(dir: Direction) -> Axis {
+        match dir {
+            Up | Down => Axis::Vertical,
+            Left | Right => Axis::Horizontal,
+        }
+    }
+}
+
+/// Wall of a weave maze: either a wall of the square grid, or the tunnel
+/// under the crossing at `(x, y)`, which is off when the tunnel is dug.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum WeaveWall {
+    Side(Wall),
+    Tunnel { x: usize, y: usize },
+}
+
+/// Square grid maze where passages can cross. A crossing cell has a
+/// passage going straight over it, and can have a tunnel under it that
+/// joins the two cells on its other sides.
+#[derive(Clone)]
+pub struct WeaveMaze {
+    grid: Maze,
+    // Axis of the passage over each crossing cell, by cell index
+    crossings: Vec<Option<Axis>>,
+    tunnels: BitVec,
+}
+
+// Space between the walls of a passage and the edges of its cell
+const INSET: f32 = 0.2;
+
+impl WeaveMaze {
+    pub fn new(width: usize, height: usize, walls_on: bool) -> WeaveMaze {
+        WeaveMaze {
+            grid: Maze::new(width, height, walls_on),
+            crossings: vec![None; width * height],
+            tunnels: BitVec::new(width * height, walls_on),
+        }
+    }
+
+    pub fn width(&self) -> usize { self.grid.width() }
+
+    pub fn height(&self) -> usize { self.grid.height() }
+
+    pub fn crossing(&self, cell: (usize, usize)) -> Option<Axis> {
+        self.crossings[self.grid.cell_index(cell)]
+    }
+
+    /// Whether `cell` can become a crossing: it is not on the edge of the
+    /// maze, and neither it nor its neighbours are crossings already.
+    pub fn can_cross(&self, cell: (usize, usize)) -> bool {
+        let (x, y) = cell;
+        let inside = x > 0 && y > 0 && x < self.width() - 1 && y < self.height() - 1;
+
+        inside
+            && [cell, (x, y - 1), (x, y + 1), (x - 1, y), (x + 1, y)]
+                .into_iter()
+                .all(|cell| self.crossing(cell).is_none())
+    }
+
+    /// Turns `cell` into a crossing with an open passage over it along
+    /// `over`. Whether the tunnel under it is dug is left to the generator.
+    pub fn add_crossing(&mut self, cell: (usize, usize), over: Axis) {
+        assert!(self.can_cross(cell));
+
+        let (x, y) = cell;
+        let index = self.grid.cell_index(cell);
+        self.crossings[index] = Some(over);
+
+        for dir in [Up, Down, Left, Right] {
+            self.grid.set_wall(&Wall { x, y, dir }, Axis::of(dir) != over);
+        }
+    }
+
+    pub fn get_wall(&self, wall: &WeaveWall) -> bool {
+        match *wall {
+            WeaveWall::Side(wall) => self.grid.get_wall(&wall),
+            WeaveWall::Tunnel { x, y } => self.tunnels.get(self.tunnel_index((x, y))),
+        }
+    }
+
+    pub fn set_wall(&mut self, wall: &WeaveWall, on: bool) {
+        match *wall {
+            WeaveWall::Side(wall) => self.grid.set_wall(&wall, on),
+            WeaveWall::Tunnel { x, y } => {
+                let index = self.tunnel_index((x, y));
+                self.tunnels.set(index, on);
+            }
+        }
+    }
+
+    fn tunnel_index(&self, cell: (usize, usize)) -> usize {
+        assert!(self.crossing(cell).is_some(), "Only crossings have tunnels");
+        self.grid.cell_index(cell)
+    }
+
+    fn step(&self, (x, y): (usize, usize), dir: Direction) -> Option<(usize, usize)> {
+        match dir {
+            Up => y.checked_sub(1).map(|y| (x, y)),
+            Down => Some((x, y + 1)).filter(|&(_, y)| y < self.height()),
+            Left => x.checked_sub(1).map(|x| (x, y)),
+            Right => Some((x + 1, y)).filter(|&(x, _)| x < self.width()),
+        }
+    }
+
+    /// Whether the passage of `cell` continues through its side in `dir`.
+    fn is_open(&self, cell: (usize, usize), dir: Direction) -> bool {
+        let (x, y) = cell;
+        let side = Wall { x, y, dir };
+
+        match (self.crossing(cell), self.step(cell, dir)) {
+            (Some(over), _) => over == Axis::of(dir) && !self.grid.get_wall(&side),
+            (None, Some(next)) if self.crossing(next).is_some_and(|over| over != Axis::of(dir)) => {
+                let (x, y) = next;
+                !self.get_wall(&WeaveWall::Tunnel { x, y })
+            }
+            (None, _) => !self.grid.get_wall(&side),
+        }
+    }
+
+    /// Lines along the side of `cell` in `dir`: the end of the passage if it
+    /// is closed, or the walls of the passage leaving the cell if it is open.
+    fn side_lines(&self, cell: (usize, usize), dir: Direction) -> Vec<Vec<Vec2>> {
+        let (outward, along) = WeaveMaze::side_vectors(dir);
+        let center = self.grid.cell_center(cell);
+        let inner = 0.5 - INSET;
+
+        if !self.is_open(cell, dir) {
+            return vec![vec![
+                center + outward * inner - along * inner,
+                center + outward * inner + along * inner,
+            ]];
+        }
+
+        [-inner, inner]
+            .into_iter()
+            .map(|offset| {
+                vec![
+                    center + outward * inner + along * offset,
+                    center + outward * 0.5 + along * offset,
+                ]
+            })
+            .collect()
+    }
+
+    /// Walls of the tunnel under a crossing, which stop at the walls of the
+    /// passage over it to leave a gap.
+    fn tunnel_lines(&self, cell: (usize, usize)) -> Vec<Vec<Vec2>> {
+        let (outward, along) = match self.crossing(cell) {
+            Some(Axis::Vertical) => WeaveMaze::side_vectors(Right),
+            _ => WeaveMaze::side_vectors(Down),
+        };
+        let center = self.grid.cell_center(cell);
+        let inner = 0.5 - INSET;
+
+        itertools::iproduct!([-1.0, 1.0], [-inner, inner])
+            .map(|(end, offset)| {
+                vec![
+                    center + outward * end * inner + along * offset,
+                    center + outward * end * 0.5 + along * offset,
+                ]
+            })
+            .collect()
+    }
+
+    /// Unit vector out of the cell through its side in `dir`, and one along
+    /// that side.
+    fn side_vectors(dir: Direction) -> (Vec2, Vec2) {
+        match dir {
+            Up => (vec2(0.0, 1.0), vec2(1.0, 0.0)),
+            Down => (vec2(0.0, -1.0), vec2(1.0, 0.0)),
+            Left => (vec2(-1.0, 0.0), vec2(0.0, 1.0)),
+            Right => (vec2(1.0, 0.0), vec2(0.0, 1.0)),
+        }
+    }
+}
+
+impl Topology for WeaveMaze {
+    type Cell = (usize, usize);
+    type Wall = WeaveWall;
+
+    fn cells(&self) -> Box<dyn Iterator<Item = (usize, usize)> + '_> {
+        self.grid.cells()
+    }
+
+    fn cell_count(&self) -> usize {
+        self.grid.cell_count()
+    }
+
+    fn cell_index(&self, cell: (usize, usize)) -> usize {
+        self.grid.cell_index(cell)
+    }
+
+    fn neighbours(&self, cell: (usize, usize)) -> Vec<((usize, usize), WeaveWall)> {
+        let (x, y) = cell;
+        let mut neighbours = Vec::with_capacity(4);
+
+        for dir in [Up, Down, Left, Right] {
+            // Crossings can only be entered along the passage over them
+            if self.crossing(cell).is_some_and(|over| over != Axis::of(dir)) {
+                continue;
+            }
+
+            let next = match self.step(cell, dir) {
+                Some(next) => next,
+                None => continue,
+            };
+
+            match self.crossing(next) {
+                Some(over) if over != Axis::of(dir) => {
+                    if let Some(beyond) = self.step(next, dir) {
+                        let (x, y) = next;
+                        neighbours.push((beyond, WeaveWall::Tunnel { x, y }));
+                    }
+                }
+                _ => neighbours.push((next, WeaveWall::Side(Wall { x, y, dir }))),
+            }
+        }
+
+        neighbours
+    }
+
+    fn walls(&self) -> Box<dyn Iterator<Item = WeaveWall> + '_> {
+        let tunnels = self
+            .cells()
+            .filter(|&cell| self.crossing(cell).is_some())
+            .map(|(x, y)| WeaveWall::Tunnel { x, y });
+
+        Box::new(self.grid.wall_iter().map(WeaveWall::Side).chain(tunnels))
+    }
+
+    fn get_wall(&self, wall: &WeaveWall) -> bool {
+        WeaveMaze::get_wall(self, wall)
+    }
+
+    fn set_wall(&mut self, wall: &WeaveWall, on: bool) {
+        WeaveMaze::set_wall(self, wall, on)
+    }
+
+    fn size(&self) -> Vec2 {
+        self.grid.size()
+    }
+
+    fn cell_center(&self, cell: (usize, usize)) -> Vec2 {
+        self.grid.cell_center(cell)
+    }
+
+    fn cell_outline(&self, cell: (usize, usize)) -> Vec<Vec2> {
+        self.grid.cell_outline(cell)
+    }
+
+    /// Grid walls are the sides of their cells, tunnels are drawn across
+    /// the middle of the passage over them.
+    fn wall_line(&self, wall: &WeaveWall) -> Vec<Vec2> {
+        match *wall {
+            WeaveWall::Side(wall) => self.grid.wall_line(&wall),
+            WeaveWall::Tunnel { x, y } => {
+                let (outward, _) = match self.crossing((x, y)) {
+                    Some(Axis::Vertical) => WeaveMaze::side_vectors(Up),
+                    _ => WeaveMaze::side_vectors(Right),
+                };
+                let center = self.grid.cell_center((x, y));
+
+                vec![center - outward * 0.5, center + outward * 0.5]
+            }
+        }
+    }
+
+    /// Passages are drawn narrower than the cells so that tunnels can be
+    /// seen going under crossings.
+    fn wall_lines(&self, wall: &WeaveWall) -> Vec<Vec<Vec2>> {
+        match *wall {
+            WeaveWall::Side(Wall { x, y, dir }) => {
+                let mut lines = self.side_lines((x, y), dir);
+
+                if let Some(next) = self.step((x, y), dir) {
+                    lines.extend(self.side_lines(next, dir.opposite()));
+                }
+
+                lines
+            }
+            WeaveWall::Tunnel { x, y } if !self.get_wall(wall) => self.tunnel_lines((x, y)),
+            WeaveWall::Tunnel { .. } => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weave_maze_tunnels_join_the_cells_around_a_crossing() {
+        let mut maze = WeaveMaze::new(3, 3, true);
+        maze.add_crossing((1, 1), Axis::Vertical);
+
+        let mut neighbours: Vec<_> = maze.neighbours((0, 1)).into_iter().map(|(cell, _)| cell).collect();
+        neighbours.sort();
+
+        assert_eq!(neighbours, vec![(0, 0), (0, 2), (2, 1)]);
+        assert!(maze.open_neighbours((0, 1)).is_empty());
+
+        maze.set_wall(&WeaveWall::Tunnel { x: 1, y: 1 }, false);
+        assert_eq!(maze.open_neighbours((0, 1)), vec![(2, 1)]);
+    }
+
+    #[test]
+    fn weave_maze_crossings_are_entered_along_the_passage_over_them() {
+        let mut maze = WeaveMaze::new(3, 3, true);
+        maze.add_crossing((1, 1), Axis::Horizontal);
+
+        let mut open = maze.open_neighbours((1, 1));
+        open.sort();
+
+        assert_eq!(open, vec![(0, 1), (2, 1)]);
+        assert_eq!(maze.neighbours((1, 1)).len(), 2);
+    }
+
+    #[test]
+    fn weave_maze_crossings_are_not_adjacent() {
+        let mut maze = WeaveMaze::new(5, 5, true);
+        maze.add_crossing((2, 2), Axis::Vertical);
+
+        assert!(!maze.can_cross((0, 2)));
+        assert!(!maze.can_cross((2, 3)));
+        assert!(maze.can_cross((3, 3)));
+    }
+
+    #[test]
+    fn weave_maze_tunnels_leave_a_gap_under_the_crossing() {
+        let mut maze = WeaveMaze::new(3, 3, true);
+        maze.add_crossing((1, 1), Axis::Vertical);
+        maze.set_wall(&WeaveWall::Tunnel { x: 1, y: 1 }, false);
+
+        let lines = maze.wall_lines(&WeaveWall::Tunnel { x: 1, y: 1 });
+        let center = maze.cell_center((1, 1));
+
+        assert_eq!(lines.len(), 4);
+        assert!(lines.iter().flatten().all(|point| (point.x - center.x).abs() >= 0.5 - INSET));
+    }
+}
@@ -0,0 +1,19 @@
+/// Edges of a `Maze` that are joined to the opposite edge.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Wrap {
+    None,
+    /// Left and right edges are joined, like the side of a cylinder.
+    Cylinder,
+    /// Both pairs of opposite edges are joined, like the surface of a torus.
+    Torus,
+}
+
+impl Wrap {
+    pub fn horizontal(self) -> bool {
+        matches!(self, Wrap::Cylinder | Wrap::Torus)
+    }
+
+    pub fn vertical(self) -> bool {
+        matches!(self, Wrap::Torus)
+    }
+}
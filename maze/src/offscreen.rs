@@ -0,0 +1,180 @@
+use std::collections::BTreeMap;
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use nannou::image::RgbaImage;
+use nannou::prelude::*;
+use nannou::wgpu;
+
+use crate::video::FrameWriter;
+
+// Samples per pixel when rendering without a window to match
+const HEADLESS_MSAA_SAMPLES: u32 = 4;
+
+/// Renders drawings into a texture instead of on the screen, and passes
+/// them to a `FrameWriter`. Frames are read back on a pool of threads and
+/// written on another one, so that rendering is not held up by them.
+pub struct OffscreenRenderer {
+    device: Arc<wgpu::DeviceQueuePair>,
+    texture: wgpu::Texture,
+    renderer: nannou::draw::Renderer,
+    capturer: wgpu::TextureCapturer,
+    frames: Sender<(usize, RgbaImage)>,
+    writer: JoinHandle<()>,
+    frame_count: usize,
+}
+
+impl OffscreenRenderer {
+    /// Renderer for frames of `size` pixels on a GPU device of its own, for
+    /// rendering without a window. Fails if there is no GPU.
+    pub fn headless(size: [u32; 2], writer: FrameWriter) -> Result<OffscreenRenderer, String> {
+        let instance = wgpu::Instance::new(wgpu::Backends::PRIMARY);
+        let options = wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            compatible_surface: None,
+            force_fallback_adapter: false,
+        };
+        let adapter = wgpu::AdapterMap::default()
+            .get_or_request(options, &instance)
+            .ok_or("Could not find a GPU to render with")?;
+        let device = adapter.get_or_request_device(wgpu::default_device_descriptor());
+
+        Ok(OffscreenRenderer::new(device, HEADLESS_MSAA_SAMPLES, size, writer))
+    }
+
+    /// Renderer for frames of `size` pixels on `device`, e.g. the one of a
+    /// window to render its frames again for a video.
+    pub fn new(
+        device: Arc<wgpu::DeviceQueuePair>,
+        msaa_samples: u32,
+        size: [u32; 2],
+        writer: FrameWriter,
+    ) -> OffscreenRenderer {
+        let texture = wgpu::TextureBuilder::new()
+            .size(size)
+            .usage(wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING)
+            .sample_count(msaa_samples)
+            .format(wgpu::TextureFormat::Rgba16Float)
+            .build(device.device());
+        let renderer = nannou::draw::RendererBuilder::new()
+            .build_from_texture_descriptor(device.device(), texture.descriptor());
+
+        let (frames, received) = mpsc::channel();
+        let writer = thread::spawn(move || write_in_order(writer, received.into_iter()));
+
+        OffscreenRenderer {
+            device,
+            texture,
+            renderer,
+            capturer: wgpu::TextureCapturer::default(),
+            frames,
+            writer,
+            frame_count: 0,
+        }
+    }
+
+    /// Area covered by the frames, centred on the origin like a window.
+    pub fn rect(&self) -> Rect {
+        let [width, height] = self.texture.size();
+        Rect::from_w_h(width as f32, height as f32)
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+
+    /// Renders `draw` as the next frame.
+    pub fn render(&mut self, draw: &Draw) {
+        let device = self.device.device();
+        let descriptor = wgpu::CommandEncoderDescriptor {
+            label: Some("Offscreen frame"),
+        };
+        let mut encoder = device.create_command_encoder(&descriptor);
+
+        self.renderer.render_to_texture(device, &mut encoder, draw, &self.texture);
+        let snapshot = self.capturer.capture(device, &mut encoder, &self.texture);
+        self.device.queue().submit(Some(encoder.finish()));
+
+        // Frames are only read once the device is polled, so wait for a
+        // free worker here rather than block on it in `read`
+        while self.capturer.active_snapshots() >= self.capturer.workers() {
+            device.poll(wgpu::Maintain::Wait);
+        }
+
+        let (index, frames) = (self.frame_count, self.frames.clone());
+        snapshot
+            .read(move |result| {
+                let image = result.expect("Could not read frame").to_owned();
+                // The writer is only gone if it failed, which it reports
+                let _ = frames.send((index, image));
+            })
+            .expect("Could not wait for a frame to be read");
+
+        self.frame_count += 1;
+    }
+
+    /// Waits until every rendered frame is written.
+    pub fn finish(self) {
+        self.capturer
+            .await_active_snapshots(self.device.device())
+            .expect("Could not wait for the frames to be read");
+
+        drop(self.frames);
+        self.writer.join().expect("Could not write the frames");
+    }
+}
+
+/// Writes the frames as they come, putting back in order the ones that
+/// were read out of order.
+fn write_in_order<I>(mut writer: FrameWriter, frames: I)
+where
+    I: Iterator<Item = (usize, RgbaImage)>,
+{
+    let mut waiting = BTreeMap::new();
+    let mut next = 0;
+
+    for (index, frame) in frames {
+        waiting.insert(index, frame);
+
+        while let Some(frame) = waiting.remove(&next) {
+            writer.write_frame(next, &frame).expect("Could not write frame");
+            next += 1;
+        }
+    }
+
+    writer.finish().expect("Could not finish writing the frames");
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn headless_rendering_writes_every_frame() {
+        let folder = std::env::temp_dir().join("maze_headless_rendering_frames");
+        let writer = FrameWriter::files(folder.clone()).unwrap();
+        let mut offscreen = match OffscreenRenderer::headless([64, 64], writer) {
+            Ok(offscreen) => offscreen,
+            // Nothing to render with on machines without a GPU
+            Err(_) => return,
+        };
+
+        let frames = 120;
+        for frame in 0..frames {
+            let draw = Draw::new();
+            draw.background().color(BLACK);
+            draw.ellipse().x(frame as f32 - 32.0).w_h(8.0, 8.0).color(WHITE);
+            offscreen.render(&draw);
+        }
+        assert_eq!(offscreen.frame_count(), frames);
+
+        offscreen.finish();
+        let written = fs::read_dir(&folder).unwrap().count();
+        fs::remove_dir_all(&folder).unwrap();
+
+        assert_eq!(written, frames);
+    }
+}
@@ -0,0 +1,397 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::Path;
+
+use nannou::color::{LinSrgba, Srgba};
+use nannou::image::{self, Rgba, RgbaImage};
+use nannou::prelude::*;
+use nannou::text::{self, rt::point};
+
+use crate::canvas::Canvas;
+
+/// Canvas drawing into an image on the CPU, so that frames can be rendered
+/// without a GPU. Shapes are anti-aliased by how much of each pixel they
+/// cover.
+pub struct Rasterizer {
+    image: RefCell<RgbaImage>,
+    // Pixels of the image per unit of the canvas
+    scale: f32,
+}
+
+impl Rasterizer {
+    pub fn new(width: u32, height: u32) -> Rasterizer {
+        Rasterizer::with_scale(width, height, 1.0)
+    }
+
+    /// Rasterizer for an image of `width` by `height` pixels, showing the
+    /// canvas scaled by `scale`, e.g. for smaller copies of a frame.
+    pub fn with_scale(width: u32, height: u32, scale: f32) -> Rasterizer {
+        Rasterizer {
+            image: RefCell::new(RgbaImage::new(width, height)),
+            scale,
+        }
+    }
+
+    /// Area of the canvas shown in the image, centred on the origin like a
+    /// window.
+    pub fn window_rect(&self) -> Rect {
+        let (width, height) = self.image.borrow().dimensions();
+        Rect::from_w_h(width as f32 / self.scale, height as f32 / self.scale)
+    }
+
+    pub fn into_image(self) -> RgbaImage {
+        self.image.into_inner()
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> image::ImageResult<()> {
+        self.image.borrow().save(path)
+    }
+
+    /// Position in the image of a point on the canvas.
+    fn to_pixels(&self, point: Vec2) -> Vec2 {
+        let (width, height) = self.image.borrow().dimensions();
+        let point = point * self.scale;
+        vec2(point.x + width as f32 / 2.0, height as f32 / 2.0 - point.y)
+    }
+
+    /// Covers the pixels within `bounds`, given in the image, by
+    /// `coverage(center)` of the pixel centres.
+    fn fill<F>(&self, bounds: Rect, color: LinSrgba, coverage: F)
+    where
+        F: Fn(Vec2) -> f32,
+    {
+        let mut image = self.image.borrow_mut();
+        let (width, height) = image.dimensions();
+        let color = Srgba::from_linear(color);
+
+        // Rows go down the image, so the top row has the lowest `y`
+        let columns = bounds.x.start.floor().max(0.0) as u32
+            ..(bounds.x.end.ceil().max(0.0) as u32).min(width);
+        let rows = bounds.y.start.floor().max(0.0) as u32
+            ..(bounds.y.end.ceil().max(0.0) as u32).min(height);
+
+        for y in rows {
+            for x in columns.clone() {
+                let covered = coverage(vec2(x as f32 + 0.5, y as f32 + 0.5)).clamp(0.0, 1.0);
+                if covered > 0.0 {
+                    blend(image.get_pixel_mut(x, y), color, covered);
+                }
+            }
+        }
+    }
+}
+
+impl Canvas for Rasterizer {
+    fn background(&self, color: LinSrgba) {
+        let Srgba { color, alpha } = Srgba::from_linear(color);
+        let pixel = Rgba([color.red, color.green, color.blue, alpha].map(to_byte));
+
+        for target in self.image.borrow_mut().pixels_mut() {
+            *target = pixel;
+        }
+    }
+
+    fn polyline(&self, points: &[Vec2], weight: f32, color: LinSrgba) {
+        let points: Vec<Vec2> = points.iter().map(|&point| self.to_pixels(point)).collect();
+        let segments: Vec<(Vec2, Vec2)> = match points.len() {
+            0 => return,
+            1 => vec![(points[0], points[0])],
+            _ => points.windows(2).map(|pair| (pair[0], pair[1])).collect(),
+        };
+
+        let radius = weight * self.scale / 2.0;
+        let coverage = |pixel, (start, end)| radius + 0.5 - segment_distance(pixel, start, end);
+        let bounds: Vec<Rect> = segments
+            .iter()
+            .map(|&(start, end)| Rect::from_corners(start, end).pad(-radius - 1.0))
+            .collect();
+
+        // Segments are bucketed by the tiles their bounds cover, so that
+        // only the ones sharing a tile are compared
+        let tile = (radius * 4.0).max(16.0);
+        let tiles = |area: Rect| {
+            let key = |value: f32| (value / tile).floor() as i64;
+            let columns = key(area.left())..=key(area.right());
+            itertools::iproduct!(columns, key(area.bottom())..=key(area.top()))
+        };
+        let mut buckets: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+        for (index, &area) in bounds.iter().enumerate() {
+            for key in tiles(area) {
+                buckets.entry(key).or_default().push(index);
+            }
+        }
+
+        for (index, (&segment, &area)) in segments.iter().zip(&bounds).enumerate() {
+            // Only segments whose bounds overlap can cover the same pixels
+            let mut nearby: Vec<usize> = tiles(area)
+                .flat_map(|key| &buckets[&key])
+                .copied()
+                .filter(|&other| other != index && bounds[other].overlap(area).is_some())
+                .collect();
+            nearby.sort_unstable();
+            nearby.dedup();
+
+            // Each pixel is covered once, by the closest segment or the
+            // first one of those as close
+            self.fill(area, color, |pixel| {
+                let covered = coverage(pixel, segment);
+                let closer = nearby.iter().any(|&other| {
+                    let other_covered = coverage(pixel, segments[other]);
+                    other_covered > covered || (other_covered == covered && other < index)
+                });
+
+                if closer { 0.0 } else { covered }
+            });
+        }
+    }
+
+    fn polygon(&self, points: &[Vec2], color: LinSrgba) {
+        let points: Vec<Vec2> = points.iter().map(|&point| self.to_pixels(point)).collect();
+        if points.len() < 3 {
+            return;
+        }
+
+        let bounds = Rect::from_corners(points[0], points[0]);
+        let bounds = points.iter().fold(bounds, |bounds, &point| bounds.stretch_to(point));
+
+        // Samples a grid of points in each pixel
+        const SAMPLES: usize = 4;
+        self.fill(bounds, color, |pixel| {
+            let inside = itertools::iproduct!(0..SAMPLES, 0..SAMPLES)
+                .map(|(i, j)| {
+                    let sample = vec2(i as f32 + 0.5, j as f32 + 0.5) / SAMPLES as f32 - 0.5;
+                    pixel + sample
+                })
+                .filter(|&sample| contains(&points, sample))
+                .count();
+
+            inside as f32 / (SAMPLES * SAMPLES) as f32
+        });
+    }
+
+    fn ellipse(&self, center: Vec2, size: Vec2, color: LinSrgba) {
+        let center = self.to_pixels(center);
+        let size = size * self.scale;
+        let radii = size / 2.0;
+        if radii.min_element() <= 0.0 {
+            return;
+        }
+
+        let bounds = Rect::from_xy_wh(center, size);
+        self.fill(bounds.pad(-1.0), color, |pixel| {
+            // Distance to the edge, scaled from the unit circle
+            let distance = (((pixel - center) / radii).length() - 1.0) * radii.min_element();
+            0.5 - distance
+        });
+    }
+
+    fn rect(&self, rect: Rect, color: LinSrgba) {
+        let corner = self.to_pixels(rect.top_left());
+        let rect = Rect::from_corners(corner, corner + rect.wh() * self.scale);
+
+        self.fill(rect, color, |pixel| {
+            let overlap = |low: f32, high: f32, at: f32| {
+                ((at + 0.5).min(high) - (at - 0.5).max(low)).max(0.0)
+            };
+
+            overlap(rect.left(), rect.right(), pixel.x)
+                * overlap(rect.bottom(), rect.top(), pixel.y)
+        });
+    }
+
+    /// Text is not wrapped, it always stays on one line.
+    fn text(&self, text: &str, center: Vec2, _width: f32, font_size: u32, color: LinSrgba) {
+        let font = text::font::default_notosans();
+        let scale = text::Scale::uniform(text::pt_to_px(font_size) * self.scale);
+        let metrics = font.v_metrics(scale);
+
+        let line_width = font
+            .layout(text, scale, point(0.0, 0.0))
+            .filter_map(|glyph| glyph.pixel_bounding_box())
+            .map(|bounds| bounds.max.x)
+            .max()
+            .unwrap_or(0) as f32;
+
+        // The line is centred horizontally and on the middle of its height
+        let center = self.to_pixels(center);
+        let origin = point(
+            center.x - line_width / 2.0,
+            center.y + (metrics.ascent + metrics.descent) / 2.0,
+        );
+
+        let mut image = self.image.borrow_mut();
+        let (image_width, image_height) = image.dimensions();
+        let color = Srgba::from_linear(color);
+
+        for glyph in font.layout(text, scale, origin) {
+            let bounds = match glyph.pixel_bounding_box() {
+                Some(bounds) => bounds,
+                None => continue,
+            };
+
+            glyph.draw(|x, y, coverage| {
+                let x = bounds.min.x + x as i32;
+                let y = bounds.min.y + y as i32;
+
+                if (0..image_width as i32).contains(&x) && (0..image_height as i32).contains(&y) {
+                    blend(image.get_pixel_mut(x as u32, y as u32), color, coverage);
+                }
+            });
+        }
+    }
+}
+
+/// Mixes `color` into `pixel` by `coverage` times its alpha.
+fn blend(pixel: &mut Rgba<u8>, color: Srgba, coverage: f32) {
+    let alpha = color.alpha * coverage;
+    let source = [color.red, color.green, color.blue];
+    let Rgba([red, green, blue, back_alpha]) = *pixel;
+    let back_alpha = back_alpha as f32 / 255.0;
+
+    let out_alpha = alpha + back_alpha * (1.0 - alpha);
+    if out_alpha <= 0.0 {
+        return;
+    }
+
+    let mix = |source: f32, back: u8| {
+        let back = back as f32 / 255.0;
+        (source * alpha + back * back_alpha * (1.0 - alpha)) / out_alpha
+    };
+
+    *pixel = Rgba([
+        to_byte(mix(source[0], red)),
+        to_byte(mix(source[1], green)),
+        to_byte(mix(source[2], blue)),
+        to_byte(out_alpha),
+    ]);
+}
+
+fn to_byte(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Distance from `point` to the segment from `start` to `end`.
+fn segment_distance(point: Vec2, start: Vec2, end: Vec2) -> f32 {
+    let along = end - start;
+    let length_squared = along.length_squared();
+    let t = if length_squared > 0.0 {
+        ((point - start).dot(along) / length_squared).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    point.distance(start + along * t)
+}
+
+/// Whether `point` is inside `polygon`, by the even-odd rule.
+fn contains(polygon: &[Vec2], point: Vec2) -> bool {
+    let mut inside = false;
+
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+        if (a.y > point.y) != (b.y > point.y) {
+            let x = a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x);
+            if point.x < x {
+                inside = !inside;
+            }
+        }
+    }
+
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use nannou::color::IntoLinSrgba;
+
+    use super::*;
+
+    fn pixel(image: &RgbaImage, x: u32, y: u32) -> [u8; 4] {
+        image.get_pixel(x, y).0
+    }
+
+    #[test]
+    fn rasterizer_fills_the_background() {
+        let raster = Rasterizer::new(4, 3);
+        raster.background(rgb8(0x10, 0x20, 0x30).into_lin_srgba());
+
+        let image = raster.into_image();
+        assert!(image.pixels().all(|pixel| pixel.0 == [0x10, 0x20, 0x30, 0xff]));
+    }
+
+    #[test]
+    fn rasterizer_draws_around_the_middle_with_y_up() {
+        let raster = Rasterizer::new(20, 20);
+        raster.background(BLACK.into_lin_srgba());
+        raster.rect(Rect::from_corners(vec2(0.0, 0.0), vec2(10.0, 10.0)), WHITE.into_lin_srgba());
+
+        let image = raster.into_image();
+        assert_eq!(pixel(&image, 15, 5), [0xff, 0xff, 0xff, 0xff]);
+        assert_eq!(pixel(&image, 5, 5), [0, 0, 0, 0xff]);
+        assert_eq!(pixel(&image, 15, 15), [0, 0, 0, 0xff]);
+    }
+
+    #[test]
+    fn rasterizer_shapes_cover_their_area() {
+        let raster = Rasterizer::new(40, 40);
+        raster.background(BLACK.into_lin_srgba());
+        raster.polyline(&[vec2(-15.0, 10.0), vec2(15.0, 10.0)], 4.0, WHITE.into_lin_srgba());
+        raster.ellipse(vec2(-10.0, -10.0), vec2(10.0, 10.0), WHITE.into_lin_srgba());
+        raster.polygon(
+            &[vec2(5.0, -5.0), vec2(15.0, -5.0), vec2(15.0, -15.0)],
+            WHITE.into_lin_srgba(),
+        );
+
+        let image = raster.into_image();
+        assert_eq!(pixel(&image, 20, 10), [0xff, 0xff, 0xff, 0xff]);
+        assert_eq!(pixel(&image, 20, 13), [0, 0, 0, 0xff]);
+        assert_eq!(pixel(&image, 10, 30), [0xff, 0xff, 0xff, 0xff]);
+        assert_eq!(pixel(&image, 33, 27), [0xff, 0xff, 0xff, 0xff]);
+        assert_eq!(pixel(&image, 27, 33), [0, 0, 0, 0xff]);
+    }
+
+    #[test]
+    fn rasterizer_covers_the_corners_of_a_polyline_once() {
+        let raster = Rasterizer::new(20, 20);
+        let points = [vec2(-5.0, 5.0), vec2(5.0, 5.0), vec2(5.0, -5.0)];
+        raster.polyline(&points, 4.0, rgba(1.0, 1.0, 1.0, 0.5).into_lin_srgba());
+
+        // The corner is as opaque as the rest of the line, not blended twice
+        let image = raster.into_image();
+        assert_eq!(pixel(&image, 14, 5), pixel(&image, 10, 5));
+        assert_eq!(pixel(&image, 14, 5)[3], 0x80);
+        assert_eq!(pixel(&image, 10, 10), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn rasterizer_covers_where_a_polyline_crosses_itself_once() {
+        let raster = Rasterizer::new(40, 40);
+        let points = [
+            vec2(-15.0, 0.0),
+            vec2(15.0, 0.0),
+            vec2(15.0, 10.0),
+            vec2(0.0, 10.0),
+            vec2(0.0, -15.0),
+        ];
+        raster.polyline(&points, 4.0, rgba(1.0, 1.0, 1.0, 0.5).into_lin_srgba());
+
+        let image = raster.into_image();
+        assert_eq!(pixel(&image, 20, 20), pixel(&image, 10, 20));
+        assert_eq!(pixel(&image, 20, 20)[3], 0x80);
+    }
+
+    #[test]
+    fn rasterizer_draws_text() {
+        let raster = Rasterizer::new(100, 40);
+        raster.text("Maze", Vec2::ZERO, 100.0, 20, WHITE.into_lin_srgba());
+
+        let image = raster.into_image();
+        let (left, right) = image
+            .enumerate_pixels()
+            .filter(|(_, _, pixel)| pixel.0[3] > 0x80)
+            .fold((u32::MAX, 0), |(left, right), (x, _, _)| (left.min(x), right.max(x)));
+
+        assert!(left < 50 && right > 50);
+        assert!(left > 10 && right < 90);
+    }
+}
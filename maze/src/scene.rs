@@ -0,0 +1,688 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::hash::Hash;
+use std::path::Path;
+
+use nannou::prelude::*;
+use serde::Deserialize;
+
+use crate::animate::*;
+use crate::generate::*;
+use crate::maze::{Mask, Topology};
+use crate::svg::{AnimatedMazeSvg, MazeSvg};
+
+type Cell<T> = <<T as MazeGenerator>::Maze as Topology>::Cell;
+
+/// A whole video as an ordered list of scenes, usually read from a TOML
+/// file. Scenes fall back on the size, wait and colours of the timeline for
+/// the settings they leave out.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Timeline {
+    pub size: [usize; 2],
+    /// Seed of the whole video. Scenes with their own seed are the same
+    /// whatever comes before them.
+    pub seed: Option<u64>,
+    /// Frames to wait after each scene.
+    pub wait: u32,
+    pub colors: Colors,
+    #[serde(rename = "scene")]
+    pub scenes: Vec<Scene>,
+    /// Size of the frames in pixels. Mazes too big for it are drawn with
+    /// smaller cells.
+    #[serde(skip)]
+    pub frame_size: [u32; 2],
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Timeline {
+            size: [38, 20],
+            seed: None,
+            wait: 60,
+            colors: Colors::default(),
+            scenes: Vec::new(),
+            frame_size: [1280, 720],
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scene {
+    pub kind: SceneKind,
+    /// Key of the generator in the registry, `kruskal` if left out.
+    pub generator: Option<String>,
+    pub shape: Shape,
+    pub size: Option<[usize; 2]>,
+    pub rings: Option<usize>,
+    pub levels: Option<usize>,
+    pub crossings: Option<usize>,
+    /// Cells the solution goes between. Negative coordinates count back
+    /// from the far side, -1 being the last cell.
+    pub start: Option<Vec<i64>>,
+    pub end: Option<Vec<i64>>,
+    pub seed: Option<u64>,
+    pub wait: Option<u32>,
+    pub colors: Colors,
+    /// Text a title maze is shaped like, and the height of its font in
+    /// cells. The font leaves room for accents and descenders, so capitals
+    /// are a little over half as high, e.g. 10 cells out of 18 rows.
+    pub text: Option<String>,
+    pub rows: Option<usize>,
+    /// Shown under the maze instead of the generator name. An empty label
+    /// shows nothing.
+    pub label: Option<String>,
+    pub transition: Transition,
+    pub transition_frames: Option<u32>,
+    /// Generation steps each frame, e.g. 0.5 for a step every other frame.
+    pub steps_per_frame: Option<f32>,
+    /// Frames the generation should take, whatever the size of the maze.
+    pub duration: Option<u32>,
+    /// Fraction of the generation spent speeding up at the start and
+    /// slowing down at the end, up to 0.5.
+    pub ramp: Option<f32>,
+    /// Points of the solution drawn each frame, and points the dot moves
+    /// along it each frame once it is drawn.
+    pub solution_speed: Option<f32>,
+    pub erase_speed: Option<f32>,
+    /// Leaves the solution on screen instead of erasing it behind the dot.
+    pub keep_path: Option<bool>,
+    /// Slides the dot between cells instead of jumping from one to the next.
+    pub smooth: Option<bool>,
+    pub solution_weight: Option<f32>,
+    pub dot_size: Option<f32>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SceneKind {
+    /// A maze being generated, then solved.
+    #[default]
+    Maze,
+    /// A maze shaped like a text, without a solution.
+    Title,
+    /// A maze being generated in the isometric view.
+    Isometric,
+    /// Nothing but the wait.
+    Wait,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Transition {
+    /// The next scene starts right after the wait.
+    #[default]
+    Cut,
+    /// The scene fades in from its background colour, and out to it after
+    /// the wait.
+    Fade,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Colors {
+    pub background: Option<Color>,
+    pub walls: Option<Color>,
+    pub highlight: Option<Color>,
+    pub accent: Option<Color>,
+    pub solution: Option<Color>,
+    /// Colour the oldest end of the solution fades from.
+    pub trail: Option<Color>,
+    pub text: Option<Color>,
+    /// Floor of isometric mazes.
+    pub floor: Option<Color>,
+}
+
+/// Colour written as `#rrggbb`.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Color(pub Rgb8);
+
+impl TryFrom<String> for Color {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, String> {
+        let hex = text.strip_prefix('#').filter(|hex| hex.len() == 6 && hex.is_ascii());
+        let channel = |i: usize| hex.and_then(|hex| u8::from_str_radix(&hex[i..i + 2], 16).ok());
+
+        match (channel(0), channel(2), channel(4)) {
+            (Some(red), Some(green), Some(blue)) => Ok(Color(rgb8(red, green, blue))),
+            _ => Err(format!("Invalid colour {}, expected #rrggbb", text)),
+        }
+    }
+}
+
+impl Colors {
+    /// These colours, with the ones left out taken from `defaults`.
+    fn or(&self, defaults: &Colors) -> Colors {
+        Colors {
+            background: self.background.or(defaults.background),
+            walls: self.walls.or(defaults.walls),
+            highlight: self.highlight.or(defaults.highlight),
+            accent: self.accent.or(defaults.accent),
+            solution: self.solution.or(defaults.solution),
+            trail: self.trail.or(defaults.trail),
+            text: self.text.or(defaults.text),
+            floor: self.floor.or(defaults.floor),
+        }
+    }
+
+    fn animator_config(&self) -> AnimatorConfig {
+        let default = AnimatorConfig::default();
+        let color = |color: Option<Color>, default| color.map_or(default, |Color(color)| color);
+
+        AnimatorConfig {
+            back_color: color(self.background, default.back_color),
+            wall_color: color(self.walls, default.wall_color),
+            highlight_color: color(self.highlight, default.highlight_color),
+            accent_color: color(self.accent, default.accent_color),
+            solution: SolutionStyle {
+                color: color(self.solution, default.solution.color),
+                trail_color: self.trail.map(|Color(color)| color),
+                ..default.solution
+            },
+            text_color: color(self.text, default.text_color),
+            ..default
+        }
+    }
+
+    fn isometric_config(&self) -> IsometricAnimatorConfig {
+        let mut config = IsometricAnimatorConfig::default();
+
+        if let Some(Color(color)) = self.background {
+            config.back_color = color;
+        }
+        if let Some(Color(color)) = self.walls {
+            config.maze.wall_color = color;
+        }
+        if let Some(Color(color)) = self.floor {
+            config.maze.floor_color = color;
+        }
+
+        config
+    }
+}
+
+impl Timeline {
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|error| error.to_string())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .map_err(|error| format!("Could not read {}: {}", path.display(), error))?;
+
+        Self::from_toml(&text).map_err(|error| format!("{}: {}", path.display(), error))
+    }
+
+    /// Animators playing the scenes one after the other.
+    pub fn animators(&self) -> Result<VecDeque<Box<dyn Animator>>, String> {
+        if self.scenes.is_empty() {
+            return Err("The timeline has no scenes".to_string());
+        }
+        if let Some(seed) = self.seed {
+            seed_generators(seed);
+        }
+
+        let mut animators = VecDeque::new();
+        for (index, scene) in self.scenes.iter().enumerate() {
+            self.add_scene(&mut animators, scene)
+                .map_err(|error| format!("Scene {}: {}", index + 1, error))?;
+        }
+
+        Ok(animators)
+    }
+
+    /// SVG picture of the first maze of the video, generated and solved,
+    /// or animated with a generation step every `step_duration` seconds.
+    pub fn svg(&self, animated: bool, step_duration: f32) -> Result<String, String> {
+        if let Some(seed) = self.seed {
+            seed_generators(seed);
+        }
+
+        let (index, scene) = self
+            .scenes
+            .iter()
+            .enumerate()
+            .find(|(_, scene)| scene.kind != SceneKind::Wait)
+            .ok_or("There is no maze to draw")?;
+
+        let picture = Picture { animated, step_duration };
+        scene
+            .build(self.size, &scene.colors.or(&self.colors), &picture)
+            .map_err(|error| format!("Scene {}: {}", index + 1, error))
+    }
+
+    fn add_scene(
+        &self,
+        animators: &mut VecDeque<Box<dyn Animator>>,
+        scene: &Scene,
+    ) -> Result<(), String> {
+        let wait = scene.wait.unwrap_or(self.wait);
+        if scene.kind == SceneKind::Wait {
+            animators.push_back(Box::new(WaitingAnimator::new(wait)));
+            return Ok(());
+        }
+
+        let colors = scene.colors.or(&self.colors);
+        let [width, height] = self.frame_size;
+        let animator = scene.animator(self.size, &colors, vec2(width as f32, height as f32))?;
+
+        match scene.transition {
+            Transition::Cut => {
+                animators.push_back(animator);
+                animators.push_back(Box::new(WaitingAnimator::new(wait)));
+            }
+            Transition::Fade => {
+                let frames = scene.transition_frames.unwrap_or(24);
+                let color = colors.animator_config().back_color;
+                animators.push_back(Box::new(FadeAnimator::new(animator, color, frames, wait)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Scene {
+    fn speed(&self) -> GenerationSpeed {
+        let default = GenerationSpeed::default();
+
+        GenerationSpeed {
+            steps_per_frame: self.steps_per_frame.unwrap_or(default.steps_per_frame),
+            duration: self.duration,
+            ramp: self.ramp.unwrap_or(default.ramp),
+        }
+    }
+
+    /// `style` with the settings of the scene.
+    fn solution_style(&self, style: SolutionStyle) -> SolutionStyle {
+        SolutionStyle {
+            draw_speed: self.solution_speed.unwrap_or(style.draw_speed),
+            erase_speed: self.erase_speed.unwrap_or(style.erase_speed),
+            keep_path: self.keep_path.unwrap_or(style.keep_path),
+            smooth: self.smooth.unwrap_or(style.smooth),
+            line_weight: self.solution_weight.unwrap_or(style.line_weight),
+            dot_size: self.dot_size.unwrap_or(style.dot_size),
+            ..style
+        }
+    }
+
+    /// The generator of the scene, made from the registry.
+    fn generator(&self, default_size: [usize; 2]) -> Result<AnyGenerator, String> {
+        let key = self.generator.as_deref().unwrap_or("kruskal");
+        let entry = find_generator(key).ok_or_else(|| format!("Unknown generator {}", key))?;
+
+        let [width, height] = self.size.unwrap_or(default_size);
+        let mut options = GeneratorOptions {
+            seed: self.seed,
+            shape: self.shape,
+            rings: self.rings,
+            levels: self.levels,
+            crossings: self.crossings,
+            ..GeneratorOptions::new(width, height)
+        };
+
+        if self.kind == SceneKind::Title {
+            let text = self.text.as_deref().ok_or("A title needs a text")?;
+            options.shape = Shape::Rect;
+            options.mask = Some(Mask::from_rendered_text(text, self.rows.unwrap_or(18))?);
+        }
+
+        entry.create(&options)
+    }
+
+    fn animator(
+        &self,
+        default_size: [usize; 2],
+        colors: &Colors,
+        frame: Vec2,
+    ) -> Result<Box<dyn Animator>, String> {
+        self.build(default_size, colors, &Animate { frame })
+    }
+
+    /// The maze of the scene between its start and end cells, made into
+    /// what `presenter` makes of it.
+    fn build<P: Presenter>(
+        &self,
+        default_size: [usize; 2],
+        colors: &Colors,
+        presenter: &P,
+    ) -> Result<P::Output, String> {
+        if self.steps_per_frame.is_some_and(|steps| steps.is_nan() || steps <= 0.0) {
+            return Err("steps_per_frame must be above 0".to_string());
+        }
+        if self.duration == Some(0) {
+            return Err("duration must be at least 1 frame".to_string());
+        }
+        let speeds = [("solution_speed", self.solution_speed), ("erase_speed", self.erase_speed)];
+        for (name, speed) in speeds {
+            if speed.is_some_and(|speed| speed.is_nan() || speed <= 0.0) {
+                return Err(format!("{} must be above 0", name));
+            }
+        }
+        let sizes = [("solution_weight", self.solution_weight), ("dot_size", self.dot_size)];
+        for (name, size) in sizes {
+            if size.is_some_and(|size| size.is_nan() || size < 0.0) {
+                return Err(format!("{} can't be negative", name));
+            }
+        }
+        let counts = [("rings", self.rings), ("levels", self.levels), ("rows", self.rows)];
+        for (name, count) in counts {
+            if count.is_some_and(|count| count < 2) {
+                return Err(format!("{} must be at least 2", name));
+            }
+        }
+        if self.transition_frames == Some(0) {
+            return Err("transition_frames must be at least 1 frame".to_string());
+        }
+        if self.text.as_deref().is_some_and(|text| text.trim().is_empty()) {
+            return Err("text can't be blank".to_string());
+        }
+
+        let [width, height] = self.size.unwrap_or(default_size);
+        // The far side of a wrapping maze is in its middle
+        let default_end = match self.shape {
+            Shape::Cylinder => [width as i64 / 2, -1],
+            Shape::Torus => [width as i64 / 2, height as i64 / 2],
+            _ => [-1, -1],
+        };
+
+        match self.generator(default_size)? {
+            AnyGenerator::Rect(generator) => {
+                let (start, end) = self.endpoints([width, height], default_end)?;
+                Ok(presenter.present(self, generator, colors, start.into(), end.into()))
+            }
+            AnyGenerator::Hex(generator) => {
+                let (start, end) = self.endpoints([width, height], [-1, -1])?;
+                Ok(presenter.present(self, generator, colors, start.into(), end.into()))
+            }
+            AnyGenerator::Delta(generator) => {
+                let (start, end) = self.endpoints([width, height], [-1, -1])?;
+                Ok(presenter.present(self, generator, colors, start.into(), end.into()))
+            }
+            AnyGenerator::Weave(generator) => {
+                let (start, end) = self.endpoints([width, height], [-1, -1])?;
+                Ok(presenter.present(self, generator, colors, start.into(), end.into()))
+            }
+            AnyGenerator::Levels(generator) => {
+                let levels = generator.initial_maze().levels();
+                let (start, end) = self.endpoints([width, height, levels], [-1, -1, -1])?;
+                Ok(presenter.present(self, generator, colors, start.into(), end.into()))
+            }
+            AnyGenerator::Polar(generator) => {
+                let maze = generator.initial_maze();
+                let cell = |coords: &[i64]| {
+                    let [ring, index] = cell(coords)?;
+                    let ring = coordinate(ring, maze.rings())?;
+                    Ok::<_, String>((ring, coordinate(index, maze.ring_size(ring))?))
+                };
+
+                let start = cell(self.start.as_deref().unwrap_or(&[0, 0]))?;
+                let end = cell(self.end.as_deref().unwrap_or(&[-1, 0]))?;
+                if start == end {
+                    return Err("start and end must be different cells".to_string());
+                }
+                Ok(presenter.present(self, generator, colors, start, end))
+            }
+        }
+    }
+
+    /// Start and end cells in a maze of `dims` cells along each axis.
+    fn endpoints<const N: usize>(
+        &self,
+        dims: [usize; N],
+        default_end: [i64; N],
+    ) -> Result<([usize; N], [usize; N]), String> {
+        let resolve = |coords: &[i64]| {
+            let coords: [i64; N] = cell(coords)?;
+            let mut resolved = [0; N];
+            for axis in 0..N {
+                resolved[axis] = coordinate(coords[axis], dims[axis])?;
+            }
+            Ok::<_, String>(resolved)
+        };
+
+        let start = resolve(self.start.as_deref().unwrap_or(&[0; N]))?;
+        let end = resolve(self.end.as_deref().unwrap_or(&default_end))?;
+        if start == end {
+            return Err("start and end must be different cells".to_string());
+        }
+        Ok((start, end))
+    }
+
+    /// Animator of the scene, with the maze fitted in `frame`.
+    fn present<T>(
+        &self,
+        generator: T,
+        colors: &Colors,
+        start: Cell<T>,
+        end: Cell<T>,
+        frame: Vec2,
+    ) -> Box<dyn Animator>
+    where
+        T: MazeGenerator + 'static,
+        <T::Maze as Topology>::Wall: Eq + Hash,
+    {
+        let speed = self.speed();
+        let size = generator.initial_maze().size();
+        let mut config = AnimatorConfig { speed, ..colors.animator_config() }.fitted(size, frame);
+        config.solution = self.solution_style(config.solution);
+
+        match self.kind {
+            SceneKind::Isometric => {
+                let config = IsometricAnimatorConfig { speed, ..colors.isometric_config() };
+                let config = config.fitted(size, frame);
+                Box::new(IsometricAnimator::new(config, generator))
+            }
+            SceneKind::Title => Box::new(MazeAnimator::title_with_config(config, generator)),
+            _ => {
+                let animator = MazeAnimator::with_config(config, generator, start, end);
+
+                match &self.label {
+                    Some(label) => {
+                        Box::new(animator.with_label(Some(label.clone()).filter(|l| !l.is_empty())))
+                    }
+                    None => Box::new(animator),
+                }
+            }
+        }
+    }
+}
+
+/// What a scene is made into once its maze and cells are known.
+trait Presenter {
+    type Output;
+
+    fn present<T>(
+        &self,
+        scene: &Scene,
+        generator: T,
+        colors: &Colors,
+        start: Cell<T>,
+        end: Cell<T>,
+    ) -> Self::Output
+    where
+        T: MazeGenerator + 'static,
+        <T::Maze as Topology>::Wall: Eq + Hash;
+}
+
+/// Scenes made into the animators of the video, in frames of `frame`.
+struct Animate {
+    frame: Vec2,
+}
+
+impl Presenter for Animate {
+    type Output = Box<dyn Animator>;
+
+    fn present<T>(
+        &self,
+        scene: &Scene,
+        generator: T,
+        colors: &Colors,
+        start: Cell<T>,
+        end: Cell<T>,
+    ) -> Self::Output
+    where
+        T: MazeGenerator + 'static,
+        <T::Maze as Topology>::Wall: Eq + Hash,
+    {
+        scene.present(generator, colors, start, end, self.frame)
+    }
+}
+
+/// Scenes made into SVG pictures. Titles have no solution.
+struct Picture {
+    animated: bool,
+    step_duration: f32,
+}
+
+impl Presenter for Picture {
+    type Output = String;
+
+    fn present<T>(
+        &self,
+        scene: &Scene,
+        generator: T,
+        colors: &Colors,
+        start: Cell<T>,
+        end: Cell<T>,
+    ) -> Self::Output
+    where
+        T: MazeGenerator + 'static,
+        <T::Maze as Topology>::Wall: Eq + Hash,
+    {
+        let mut config = colors.animator_config();
+        config.solution = scene.solution_style(config.solution);
+        let solution = SolutionAnimatorConfig {
+            style: config.solution,
+            ..SolutionAnimatorConfig::new(Vec2::ZERO, Vec2::ZERO)
+        };
+        let solved = scene.kind != SceneKind::Title;
+
+        if self.animated {
+            let svg = AnimatedMazeSvg::new(generator, &config, self.step_duration);
+            let svg = if solved { svg.with_solution(&solution, start, end) } else { svg };
+            return svg.into_svg();
+        }
+
+        let mut maze = generator.initial_maze();
+        for (wall, on) in generator {
+            maze.set_wall(&wall, on);
+        }
+
+        let svg = MazeSvg::new(&maze, &config);
+        let svg = if solved { svg.with_solution(&solution, start, end) } else { svg };
+        svg.to_svg()
+    }
+}
+
+/// `coords` as the `N` coordinates of a cell.
+fn cell<const N: usize>(coords: &[i64]) -> Result<[i64; N], String> {
+    coords
+        .try_into()
+        .map_err(|_| format!("Expected a cell of {} coordinates, got {:?}", N, coords))
+}
+
+/// Coordinate along an axis of `len` cells. Negative values count back from
+/// the end.
+fn coordinate(value: i64, len: usize) -> Result<usize, String> {
+    let resolved = if value < 0 { len as i64 + value } else { value };
+
+    if (0..len as i64).contains(&resolved) {
+        Ok(resolved as usize)
+    } else {
+        Err(format!("Coordinate {} is outside of a maze {} cells wide", value, len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeline_reads_scenes_and_falls_back_on_its_defaults() {
+        let timeline = Timeline::from_toml(
+            r##"
+            wait = 10
+            colors = { walls = "#ff8000" }
+
+            [[scene]]
+            generator = "binary-tree"
+            size = [6, 4]
+            start = [-1, 0]
+            transition = "fade"
+
+            [[scene]]
+            kind = "wait"
+            wait = 5
+            "##,
+        )
+        .unwrap();
+
+        assert_eq!(timeline.size, [38, 20]);
+        assert_eq!(timeline.scenes.len(), 2);
+        assert_eq!(timeline.scenes[0].transition, Transition::Fade);
+        assert_eq!(timeline.colors.walls, Some(Color(rgb8(0xff, 0x80, 0x00))));
+
+        // The faded scene holds its wait, the waiting scene has nothing else
+        assert_eq!(timeline.animators().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn timeline_rejects_invalid_scenes() {
+        let error = |text: &str| Timeline::from_toml(text).unwrap().animators().err();
+
+        assert!(Timeline::from_toml("[[scene]]\ngenrator = \"kruskal\"").is_err());
+        assert!(Timeline::from_toml("colors = { walls = \"blue\" }").is_err());
+        assert!(error("[[scene]]\ngenerator = \"nope\"").is_some());
+        assert!(error("[[scene]]\nshape = \"hex\"\ngenerator = \"binary-tree\"").is_some());
+        assert!(error("[[scene]]\nsize = [4, 4]\nend = [4, 0]").is_some());
+        assert!(error("[[scene]]\nkind = \"title\"").is_some());
+        assert!(error("[[scene]]\nsteps_per_frame = 0.0").is_some());
+        assert!(error("[[scene]]\nsteps_per_frame = 2.5\nramp = 0.2").is_none());
+        assert!(error("[[scene]]\nerase_speed = -1.0").is_some());
+        assert!(error("[[scene]]\ndot_size = -2.0").is_some());
+        assert!(error("[[scene]]\nsolution_speed = 0.5\nkeep_path = true").is_none());
+        assert!(error("[[scene]]\nsize = [4, 4]\nend = [-3, 0]").is_none());
+        assert!(error("[[scene]]\nshape = \"levels\"\nlevels = 1").is_some());
+        assert!(error("[[scene]]\nshape = \"levels\"\nlevels = 2").is_none());
+        assert!(error("[[scene]]\nshape = \"polar\"\nrings = 1").is_some());
+        assert!(error("[[scene]]\ntransition = \"fade\"\ntransition_frames = 0").is_some());
+        assert!(error("[[scene]]\nkind = \"title\"\ntext = \" \"").is_some());
+        assert!(error("[[scene]]\nkind = \"title\"\ntext = \"Hi\"\nrows = 1").is_some());
+        assert!(error("[[scene]]\nstart = [1, 1]\nend = [1, 1]").is_some());
+        assert!(error("[[scene]]\nshape = \"polar\"\nend = [0, 0]").is_some());
+        assert!(error("").is_some());
+
+        // Errors say which scene is wrong
+        let second = error("[[scene]]\n\n[[scene]]\nkind = \"title\"\ntext = \" \"");
+        assert!(second.unwrap().starts_with("Scene 2: "));
+    }
+
+    #[test]
+    fn timeline_draws_its_first_maze_as_svg() {
+        let timeline = Timeline::from_toml(
+            "[[scene]]\nkind = \"wait\"\n\n[[scene]]\nshape = \"hex\"\nsize = [4, 3]",
+        )
+        .unwrap();
+
+        let still = timeline.svg(false, 0.1).unwrap();
+        let animated = timeline.svg(true, 0.1).unwrap();
+
+        assert!(still.starts_with("<svg") && still.contains("<ellipse"));
+        assert!(animated.contains("<set") && animated.contains("<animate"));
+
+        let nothing_to_draw = Timeline::from_toml("[[scene]]\nkind = \"wait\"").unwrap();
+        assert!(nothing_to_draw.svg(false, 0.1).is_err());
+    }
+
+    #[test]
+    fn default_scenes_are_valid() {
+        let timeline = Timeline::from_toml(include_str!("../scenes/default.toml")).unwrap();
+
+        assert!(timeline.animators().is_ok());
+    }
+}
@@ -1,32 +1,22 @@
-use crate::maze::{wall::Direction::*, Wall};
 use std::collections::{HashMap, HashSet};
 
-use crate::maze::Maze;
-
-fn get_reachable_cells(maze: &Maze, cell: (usize, usize)) -> Vec<(usize, usize)> {
-    let (x, y) = cell;
-
-    vec![
-        (x + 1, y, Right),
-        (x.wrapping_sub(1), y, Left),
-        (x, y + 1, Down),
-        (x, y.wrapping_sub(1), Up),
-    ]
-    .into_iter()
-    .filter(|(x, y, _)| (0..maze.width()).contains(x) && (0..maze.height()).contains(y))
-    .filter(|&(_, _, dir)| maze.get_wall(&Wall { x, y, dir }) != true)
-    .map(|(x, y, _)| (x, y))
-    .collect()
+use nannou::prelude::*;
+
+use crate::maze::Topology;
+
+fn get_reachable_cells<M: Topology>(maze: &M, cell: M::Cell) -> Vec<M::Cell> {
+    maze.open_neighbours(cell)
 }
 
-pub fn solve(maze: &Maze, start: (usize, usize), end: (usize, usize)) -> Vec<(usize, usize)> {
+/// Path from `start` to `end`, or `None` if no passage joins them.
+pub fn solve<M: Topology>(maze: &M, start: M::Cell, end: M::Cell) -> Option<Vec<M::Cell>> {
     let mut came_from = HashMap::new();
     let mut checked_cells = HashSet::new();
     // Solve the maze backwards to make the path creation easier
     let mut cells_to_check = vec![end];
 
-    while !came_from.contains_key(&start) {
-        let cell = cells_to_check.pop().unwrap();
+    while start != end && !came_from.contains_key(&start) {
+        let cell = cells_to_check.pop()?;
         checked_cells.insert(cell);
 
         let unchecked_reachable_cells = get_reachable_cells(maze, cell)
@@ -44,11 +34,45 @@ pub fn solve(maze: &Maze, start: (usize, usize), end: (usize, usize)) -> Vec<(us
         let next_cell = came_from.get(travel_path.last().unwrap()).unwrap();
         travel_path.push(*next_cell);
     }
-    travel_path
+    Some(travel_path)
+}
+
+/// Lines through the centres of the cells along `path`. The path is split
+/// in parts where it goes through a passage between cells that are not
+/// drawn next to each other, e.g. across the edges of a wrapping maze.
+pub fn path_lines<M: Topology>(maze: &M, path: &[M::Cell]) -> Vec<Vec<Vec2>> {
+    let first = path.first().map(|&cell| maze.cell_center(cell));
+    let mut parts: Vec<Vec<Vec2>> = vec![first.into_iter().collect()];
+    let extend = |parts: &mut Vec<Vec<Vec2>>, point: Vec2| {
+        let part = parts.last_mut().unwrap();
+        if part.last() != Some(&point) {
+            part.push(point);
+        }
+    };
+
+    for (&cell, &next) in path.iter().zip(path.iter().skip(1)) {
+        let jump = maze
+            .neighbours(cell)
+            .into_iter()
+            .filter(|(neighbour, wall)| *neighbour == next && !maze.get_wall(wall))
+            .find_map(|(_, wall)| maze.jump(cell, &wall));
+
+        if let Some((exit, entry)) = jump {
+            extend(&mut parts, exit);
+            parts.push(vec![entry]);
+        }
+
+        extend(&mut parts, maze.cell_center(next));
+    }
+
+    parts
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::maze::hex::HexDirection;
+    use crate::maze::{wall::Direction::*, HexMaze, HexWall, LevelMaze, LevelWall, Maze, Wall, Wrap};
+
     use super::*;
 
     #[test]
@@ -99,7 +123,7 @@ mod tests {
         let end = (1, 0);
         let expected = vec![(0, 0), (0, 1), (1, 1), (1, 0)];
 
-        assert_eq!(solve(&maze, start, end), expected);
+        assert_eq!(solve(&maze, start, end), Some(expected));
     }
 
     #[test]
@@ -114,6 +138,64 @@ mod tests {
         let end = (2, 2);
         let expected = vec![(0, 0), (0, 1), (1, 1), (2, 1), (2, 2)];
 
-        assert_eq!(solve(&maze, start, end), expected);
+        assert_eq!(solve(&maze, start, end), Some(expected));
+    }
+
+    #[test]
+    fn solve_works_for_hex_maze() {
+        let mut maze = HexMaze::new(2, 2, true);
+        maze.set_wall(&HexWall { x: 0, y: 0, dir: HexDirection::East }, false);
+        maze.set_wall(&HexWall { x: 1, y: 0, dir: HexDirection::SouthEast }, false);
+
+        let expected = vec![(0, 0), (1, 0), (1, 1)];
+
+        assert_eq!(solve(&maze, (0, 0), (1, 1)), Some(expected));
+    }
+
+    #[test]
+    fn solve_finds_no_path_to_walled_off_cells() {
+        let mut maze = Maze::new(2, 2, true);
+        maze.set_wall(&Wall { x: 0, y: 0, dir: Right }, false);
+
+        assert_eq!(solve(&maze, (0, 0), (1, 0)), Some(vec![(0, 0), (1, 0)]));
+        assert_eq!(solve(&maze, (0, 0), (1, 1)), None);
+        assert_eq!(solve(&maze, (1, 1), (1, 1)), Some(vec![(1, 1)]));
+    }
+
+    #[test]
+    fn solve_goes_across_the_edges_of_a_torus() {
+        let mut maze = Maze::with_wrap(4, 4, Wrap::Torus, true).unwrap();
+        maze.set_wall(&Wall { x: 0, y: 0, dir: Up }, false);
+        maze.set_wall(&Wall { x: 0, y: 3, dir: Left }, false);
+
+        let solution = solve(&maze, (0, 0), (3, 3)).unwrap();
+
+        assert_eq!(solution, vec![(0, 0), (0, 3), (3, 3)]);
+    }
+
+    #[test]
+    fn solve_takes_the_stairs_between_levels() {
+        let mut maze = LevelMaze::new(2, 2, 2, true);
+        maze.set_wall(&LevelWall::Stairs { x: 1, y: 0, level: 0 }, false);
+        maze.set_wall(&LevelWall::Side { level: 0, wall: Wall { x: 0, y: 0, dir: Right } }, false);
+
+        let solution = solve(&maze, (0, 0, 0), (1, 0, 1)).unwrap();
+
+        assert_eq!(solution, vec![(0, 0, 0), (1, 0, 0), (1, 0, 1)]);
+    }
+
+    #[test]
+    fn path_lines_split_across_the_edges_of_a_torus() {
+        let mut maze = Maze::with_wrap(4, 4, Wrap::Torus, true).unwrap();
+        maze.set_wall(&Wall { x: 0, y: 0, dir: Up }, false);
+        maze.set_wall(&Wall { x: 0, y: 3, dir: Left }, false);
+
+        let lines = path_lines(&maze, &[(0, 0), (0, 3), (3, 3)]);
+
+        // Each part goes from a cell centre or an edge to an edge or a centre
+        assert_eq!(lines.iter().map(Vec::len).collect::<Vec<_>>(), vec![2, 3, 2]);
+        assert_eq!(lines[0][0], maze.cell_center((0, 0)));
+        assert_eq!(lines[1][1], maze.cell_center((0, 3)));
+        assert_eq!(lines[2][1], maze.cell_center((3, 3)));
     }
 }
@@ -0,0 +1,579 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::hash::Hash;
+use std::io;
+use std::path::Path;
+
+use nannou::color::{IntoLinSrgba, LinSrgba, Srgba};
+use nannou::prelude::*;
+use nannou::text;
+
+use crate::animate::{AnimatorConfig, SolutionAnimatorConfig};
+use crate::canvas::Canvas;
+use crate::draw::Draw as MazeDraw;
+use crate::generate::MazeGenerator;
+use crate::maze::Topology;
+use crate::solve::{path_lines, solve};
+
+enum Element {
+    Rect { rect: Rect, color: LinSrgba },
+    Polyline { points: Vec<Vec2>, weight: f32, color: LinSrgba },
+    Polygon { points: Vec<Vec2>, color: LinSrgba },
+    Ellipse { center: Vec2, size: Vec2, color: LinSrgba },
+    Text { text: String, center: Vec2, font_size: u32, color: LinSrgba },
+}
+
+/// Canvas recording what is drawn on it as an SVG picture. Lines of the
+/// same style drawn one after the other are joined where they meet, so a
+/// maze is made of a few long strokes rather than one per wall.
+pub struct SvgCanvas {
+    size: Vec2,
+    elements: RefCell<Vec<Element>>,
+}
+
+impl SvgCanvas {
+    pub fn new(size: Vec2) -> SvgCanvas {
+        SvgCanvas {
+            size,
+            elements: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn window_rect(&self) -> Rect {
+        Rect::from_wh(self.size)
+    }
+
+    pub fn to_svg(&self) -> String {
+        let mut svg = svg_header(self.size);
+        self.write_elements(&mut svg);
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_svg())
+    }
+
+    /// Writes what is drawn on the canvas to `svg`, without the tags
+    /// around a picture.
+    fn write_elements(&self, svg: &mut String) {
+        let elements = self.elements.borrow();
+        let mut index = 0;
+        while index < elements.len() {
+            if let Element::Polyline { weight, color, .. } = elements[index] {
+                // Runs of lines of the same style become a single path
+                let mut lines = Vec::new();
+                while let Some(Element::Polyline { points, .. }) = elements
+                    .get(index)
+                    .filter(|element| same_style(element, weight, color))
+                {
+                    lines.push(points.clone());
+                    index += 1;
+                }
+
+                write_lines(svg, merge_lines(lines), weight, color);
+            } else {
+                write_element(svg, &elements[index]);
+                index += 1;
+            }
+        }
+    }
+
+    fn push(&self, element: Element) {
+        self.elements.borrow_mut().push(element);
+    }
+}
+
+impl Canvas for SvgCanvas {
+    /// The background covers everything drawn before, which is dropped.
+    fn background(&self, color: LinSrgba) {
+        self.elements.borrow_mut().clear();
+        self.push(Element::Rect { rect: self.window_rect(), color });
+    }
+
+    fn polyline(&self, points: &[Vec2], weight: f32, color: LinSrgba) {
+        if !points.is_empty() {
+            self.push(Element::Polyline { points: points.to_vec(), weight, color });
+        }
+    }
+
+    fn polygon(&self, points: &[Vec2], color: LinSrgba) {
+        self.push(Element::Polygon { points: points.to_vec(), color });
+    }
+
+    fn ellipse(&self, center: Vec2, size: Vec2, color: LinSrgba) {
+        self.push(Element::Ellipse { center, size, color });
+    }
+
+    fn rect(&self, rect: Rect, color: LinSrgba) {
+        self.push(Element::Rect { rect, color });
+    }
+
+    fn text(&self, text: &str, center: Vec2, _width: f32, font_size: u32, color: LinSrgba) {
+        let text = text.to_string();
+        self.push(Element::Text { text, center, font_size, color });
+    }
+}
+
+/// Picture of a maze in the same style as its animation, optionally with
+/// the path between two cells marked on it.
+pub struct MazeSvg<'a, M: Topology> {
+    maze: &'a M,
+    config: &'a AnimatorConfig,
+    solution: Option<(&'a SolutionAnimatorConfig, M::Cell, M::Cell)>,
+}
+
+impl<'a, M: Topology> MazeSvg<'a, M> {
+    pub fn new(maze: &'a M, config: &'a AnimatorConfig) -> Self {
+        MazeSvg { maze, config, solution: None }
+    }
+
+    /// Adds the path from `start` to `end`, in the colour, weight and dot
+    /// size of `config`. The dots mark both ends of the path.
+    pub fn with_solution(
+        mut self,
+        config: &'a SolutionAnimatorConfig,
+        start: M::Cell,
+        end: M::Cell,
+    ) -> Self {
+        self.solution = Some((config, start, end));
+        self
+    }
+
+    pub fn to_svg(&self) -> String {
+        let wall_size = self.config.wall_size;
+        // One cell of margin around the maze
+        let canvas = SvgCanvas::new((self.maze.size() + 2.0) * wall_size);
+        canvas.background(self.config.back_color.into_lin_srgba());
+
+        let maze_draw = MazeDraw::new(&canvas, self.maze, self.config.wall_color, wall_size);
+        for wall in self.maze.walls() {
+            maze_draw.wall(&wall);
+        }
+
+        if let Some((config, start, end)) = self.solution {
+            let color = config.style.color.into_lin_srgba();
+            // Only the dots are drawn when the end can't be reached
+            let path = solve(self.maze, start, end).unwrap_or_default();
+
+            for line in path_lines(self.maze, &path).into_iter().filter(|line| line.len() > 1) {
+                let points: Vec<Vec2> = line.into_iter().map(|point| point * wall_size).collect();
+                canvas.polyline(&points, config.style.line_weight, color);
+            }
+
+            for cell in [start, end] {
+                let size = vec2(config.style.dot_size, config.style.dot_size);
+                canvas.ellipse(maze_draw.cell_center(cell), size, color);
+            }
+        }
+
+        canvas.to_svg()
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_svg())
+    }
+}
+
+type Cell<T> = <<T as MazeGenerator>::Maze as Topology>::Cell;
+
+/// Picture of a maze being generated, one step every `step_duration`
+/// seconds, and then solved, animated with SMIL. Walls switch between how
+/// they look on and off as they are turned on and off.
+pub struct AnimatedMazeSvg<'a, T: MazeGenerator> {
+    generator: T,
+    config: &'a AnimatorConfig,
+    step_duration: f32,
+    solution: Option<(&'a SolutionAnimatorConfig, Cell<T>, Cell<T>)>,
+}
+
+impl<'a, T> AnimatedMazeSvg<'a, T>
+where
+    T: MazeGenerator,
+    <T::Maze as Topology>::Wall: Eq + Hash,
+{
+    pub fn new(generator: T, config: &'a AnimatorConfig, step_duration: f32) -> Self {
+        AnimatedMazeSvg { generator, config, step_duration, solution: None }
+    }
+
+    /// Adds the path from `start` to `end`, drawn a cell per step once the
+    /// maze is generated.
+    pub fn with_solution(
+        mut self,
+        config: &'a SolutionAnimatorConfig,
+        start: Cell<T>,
+        end: Cell<T>,
+    ) -> Self {
+        self.solution = Some((config, start, end));
+        self
+    }
+
+    pub fn into_svg(self) -> String {
+        let initial_maze = self.generator.initial_maze();
+        let mut maze = initial_maze.clone();
+        let mut changes: HashMap<_, Vec<(f32, bool)>> = HashMap::new();
+        let mut time = 0.0;
+
+        for (wall, on) in self.generator {
+            changes.entry(wall).or_default().push((time, on));
+            maze.set_wall(&wall, on);
+            time += self.step_duration;
+        }
+
+        let wall_size = self.config.wall_size;
+        let size = (maze.size() + 2.0) * wall_size;
+        let mut svg = svg_header(size);
+        let color = self.config.back_color.into_lin_srgba();
+        write_element(&mut svg, &Element::Rect { rect: Rect::from_wh(size), color });
+
+        for wall in initial_maze.walls() {
+            let changes = changes.remove(&wall).unwrap_or_default();
+            let generated_on = maze.get_wall(&wall);
+
+            // The wall is drawn both on and off in the generated maze, as
+            // the still picture draws it, e.g. open tunnels of weave mazes
+            for look in [true, false] {
+                let shown = initial_maze.get_wall(&wall) == look;
+                if !shown && changes.iter().all(|&(_, on)| on != look) {
+                    continue;
+                }
+
+                let canvas = SvgCanvas::new(size);
+                maze.set_wall(&wall, look);
+                MazeDraw::new(&canvas, &maze, self.config.wall_color, wall_size).wall(&wall);
+
+                let mut elements = String::new();
+                canvas.write_elements(&mut elements);
+                if elements.is_empty() {
+                    continue;
+                }
+
+                let visibility = if shown { "visible" } else { "hidden" };
+                writeln!(svg, r#"<g visibility="{}">"#, visibility).unwrap();
+                svg.push_str(&elements);
+                for &(time, on) in &changes {
+                    svg.push_str(&visibility_set(time, on == look));
+                }
+                svg.push_str("</g>\n");
+            }
+
+            maze.set_wall(&wall, generated_on);
+        }
+
+        if let Some((config, start, end)) = self.solution {
+            let color = config.style.color.into_lin_srgba();
+            let dot = |cell, begin: Option<f32>| {
+                let center = maze.cell_center(cell) * wall_size;
+                let set = begin.map(|time| visibility_set(time, true)).unwrap_or_default();
+                format!(
+                    r#"<ellipse cx="{}" cy="{}" rx="{}" ry="{}" visibility="{}" {}>{}</ellipse>"#,
+                    number(center.x),
+                    number(-center.y),
+                    number(config.style.dot_size / 2.0),
+                    number(config.style.dot_size / 2.0),
+                    if begin.is_some() { "hidden" } else { "visible" },
+                    paint("fill", color),
+                    set,
+                )
+            };
+
+            svg.push_str(&dot(start, None));
+            svg.push('\n');
+
+            let path = solve(&maze, start, end).unwrap_or_default();
+            for line in path_lines(&maze, &path).into_iter().filter(|line| line.len() > 1) {
+                let points: Vec<Vec2> = line.iter().map(|&point| point * wall_size).collect();
+                let duration = (points.len().max(2) - 1) as f32 * self.step_duration;
+
+                // The dash covering the line is moved onto it from behind
+                writeln!(
+                    svg,
+                    concat!(
+                        r#"<path d="M{}" visibility="hidden" pathLength="1" "#,
+                        r#"stroke-dasharray="1 2" stroke-dashoffset="1" {}>{}"#,
+                        r#"<animate attributeName="stroke-dashoffset" from="1" to="0" "#,
+                        r#"begin="{}s" dur="{}s" fill="freeze"/></path>"#,
+                    ),
+                    points_list(&points).replace(' ', " L"),
+                    stroke(config.style.line_weight, color),
+                    visibility_set(time, true),
+                    number(time),
+                    number(duration),
+                )
+                .unwrap();
+                time += duration;
+            }
+
+            svg.push_str(&dot(end, Some(time)));
+            svg.push('\n');
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    pub fn save<P: AsRef<Path>>(self, path: P) -> io::Result<()> {
+        fs::write(path, self.into_svg())
+    }
+}
+
+/// Opening tag of a picture of `size`, with the origin in the middle.
+fn svg_header(size: Vec2) -> String {
+    format!(
+        concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" "#,
+            r#"viewBox="{} {} {} {}">"#,
+            "\n",
+        ),
+        number(size.x),
+        number(size.y),
+        number(-size.x / 2.0),
+        number(-size.y / 2.0),
+        number(size.x),
+        number(size.y),
+    )
+}
+
+fn visibility_set(time: f32, visible: bool) -> String {
+    format!(
+        r#"<set attributeName="visibility" to="{}" begin="{}s" fill="freeze"/>"#,
+        if visible { "visible" } else { "hidden" },
+        number(time),
+    )
+}
+
+fn same_style(element: &Element, weight: f32, color: LinSrgba) -> bool {
+    matches!(*element, Element::Polyline { weight: w, color: c, .. } if w == weight && c == color)
+}
+
+fn write_element(svg: &mut String, element: &Element) {
+    match element {
+        Element::Rect { rect, color } => writeln!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" {}/>"#,
+            number(rect.left()),
+            number(-rect.top()),
+            number(rect.w()),
+            number(rect.h()),
+            paint("fill", *color),
+        ),
+        Element::Polyline { .. } => unreachable!("Lines are written together"),
+        Element::Polygon { points, color } => writeln!(
+            svg,
+            r#"<polygon points="{}" {}/>"#,
+            points_list(points),
+            paint("fill", *color),
+        ),
+        Element::Ellipse { center, size, color } => writeln!(
+            svg,
+            r#"<ellipse cx="{}" cy="{}" rx="{}" ry="{}" {}/>"#,
+            number(center.x),
+            number(-center.y),
+            number(size.x / 2.0),
+            number(size.y / 2.0),
+            paint("fill", *color),
+        ),
+        Element::Text { text, center, font_size, color } => writeln!(
+            svg,
+            concat!(
+                r#"<text x="{}" y="{}" font-family="Noto Sans, sans-serif" font-size="{}" "#,
+                r#"text-anchor="middle" dominant-baseline="middle" {}>{}</text>"#,
+            ),
+            number(center.x),
+            number(-center.y),
+            number(text::pt_to_px(*font_size)),
+            paint("fill", *color),
+            escape(text),
+        ),
+    }
+    .unwrap();
+}
+
+fn write_lines(svg: &mut String, lines: Vec<Vec<Vec2>>, weight: f32, color: LinSrgba) {
+    let path = lines
+        .iter()
+        .map(|line| {
+            // A single point is drawn as a dot by the round caps
+            let line = if line.len() == 1 { vec![line[0]; 2] } else { line.clone() };
+            format!("M{}", points_list(&line).replace(' ', " L"))
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    writeln!(svg, r#"<path d="{}" {}/>"#, path, stroke(weight, color)).unwrap();
+}
+
+/// Attributes of a line of `weight` and `color`, with round caps and joins.
+fn stroke(weight: f32, color: LinSrgba) -> String {
+    format!(
+        r#"fill="none" stroke-width="{}" stroke-linecap="round" stroke-linejoin="round" {}"#,
+        number(weight),
+        paint("stroke", color),
+    )
+}
+
+/// Joins lines that end where another one starts or ends into longer
+/// lines.
+fn merge_lines(lines: Vec<Vec<Vec2>>) -> Vec<Vec<Vec2>> {
+    // Points are compared to a thousandth of a pixel
+    let key = |point: Vec2| ((point.x * 1000.0).round() as i64, (point.y * 1000.0).round() as i64);
+
+    let mut ends: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+    for (index, line) in lines.iter().enumerate() {
+        ends.entry(key(line[0])).or_default().push(index);
+        ends.entry(key(line[line.len() - 1])).or_default().push(index);
+    }
+
+    let mut used = vec![false; lines.len()];
+    let mut merged = Vec::new();
+
+    for index in 0..lines.len() {
+        if used[index] {
+            continue;
+        }
+        used[index] = true;
+        let mut line = lines[index].clone();
+
+        // Extend the end of the line, then turn it around to extend its start
+        for _ in 0..2 {
+            loop {
+                let end = key(line[line.len() - 1]);
+                let next = match ends[&end].iter().copied().find(|&next| !used[next]) {
+                    Some(next) => next,
+                    None => break,
+                };
+                used[next] = true;
+
+                let mut other = lines[next].clone();
+                if key(other[0]) != end {
+                    other.reverse();
+                }
+                line.extend(other.into_iter().skip(1));
+            }
+            line.reverse();
+        }
+
+        merged.push(line);
+    }
+
+    merged
+}
+
+fn points_list(points: &[Vec2]) -> String {
+    points
+        .iter()
+        .map(|point| format!("{},{}", number(point.x), number(-point.y)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `attribute` set to the colour, with its opacity when it is not opaque.
+fn paint(attribute: &str, color: LinSrgba) -> String {
+    let Srgba { color, alpha } = Srgba::from_linear(color);
+    let byte = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    let hex = format!("#{:02x}{:02x}{:02x}", byte(color.red), byte(color.green), byte(color.blue));
+
+    if alpha < 1.0 {
+        format!(r#"{}="{}" {}-opacity="{}""#, attribute, hex, attribute, number(alpha))
+    } else {
+        format!(r#"{}="{}""#, attribute, hex)
+    }
+}
+
+/// Number rounded to a hundredth, without trailing zeros.
+fn number(value: f32) -> String {
+    // Adding zero turns -0 into 0
+    format!("{}", (value * 100.0).round() / 100.0 + 0.0)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::KruskalsGenerator;
+    use crate::maze::{wall::Direction, Maze, Wall};
+
+    #[test]
+    fn merge_lines_joins_lines_end_to_end() {
+        let lines = vec![
+            vec![vec2(0.0, 0.0), vec2(1.0, 0.0)],
+            vec![vec2(1.0, 1.0), vec2(0.0, 1.0)],
+            vec![vec2(1.0, 0.0), vec2(1.0, 1.0)],
+            vec![vec2(5.0, 5.0), vec2(6.0, 5.0)],
+        ];
+
+        let merged = merge_lines(lines);
+
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].len(), 4);
+    }
+
+    #[test]
+    fn maze_svg_draws_all_walls_as_one_path() {
+        let maze = Maze::new(3, 2, true);
+        let svg = MazeSvg::new(&maze, &AnimatorConfig::default()).to_svg();
+
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<path").count(), 1);
+        assert_eq!(svg.matches("<rect").count(), 1);
+    }
+
+    #[test]
+    fn maze_svg_marks_the_solution() {
+        let mut maze = Maze::new(2, 2, true);
+        maze.set_wall(&Wall { x: 0, y: 0, dir: Direction::Right }, false);
+        let solution = SolutionAnimatorConfig::new(Vec2::ZERO, Vec2::ZERO);
+
+        let svg = MazeSvg::new(&maze, &AnimatorConfig::default())
+            .with_solution(&solution, (0, 0), (1, 0))
+            .to_svg();
+
+        assert_eq!(svg.matches(r##"stroke="#a52422""##).count(), 1);
+        assert_eq!(svg.matches(r##"<ellipse"##).count(), 2);
+    }
+
+    #[test]
+    fn maze_svg_only_marks_the_ends_of_unsolvable_mazes() {
+        let maze = Maze::new(2, 2, true);
+        let solution = SolutionAnimatorConfig::new(Vec2::ZERO, Vec2::ZERO);
+
+        let svg = MazeSvg::new(&maze, &AnimatorConfig::default())
+            .with_solution(&solution, (0, 0), (1, 1))
+            .to_svg();
+
+        assert_eq!(svg.matches(r##"stroke="#a52422""##).count(), 0);
+        assert_eq!(svg.matches(r##"<ellipse"##).count(), 2);
+    }
+
+    #[test]
+    fn animated_maze_svg_shows_every_step() {
+        let generator = KruskalsGenerator::new(3, 2);
+        let solution = SolutionAnimatorConfig::new(Vec2::ZERO, Vec2::ZERO);
+
+        let svg = AnimatedMazeSvg::new(generator, &AnimatorConfig::default(), 0.1)
+            .with_solution(&solution, (0, 0), (2, 1))
+            .into_svg();
+
+        // 5 walls carved, and the end marker shown after the solution
+        assert_eq!(svg.matches(r#"to="hidden""#).count(), 5);
+        assert!(svg.matches("<animate").count() >= 1);
+        assert_eq!(svg.matches("<ellipse").count(), 2);
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    fn animated_maze_svg_draws_carved_weave_walls_as_passages() {
+        let generator = KruskalsGenerator::weave(5, 5, 3);
+        let svg = AnimatedMazeSvg::new(generator, &AnimatorConfig::default(), 0.1).into_svg();
+
+        // Each wall carved is hidden as the sides of its passage are shown
+        let hidden = svg.matches(r#"to="hidden""#).count();
+        assert!(hidden > 0);
+        assert_eq!(svg.matches(r#"to="visible""#).count(), hidden);
+    }
+}
@@ -0,0 +1,186 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+
+use nannou::image::RgbaImage;
+
+/// Encoder the frames are piped into, as raw RGBA pixels. The command line
+/// is the one of ffmpeg.
+pub struct EncoderConfig {
+    pub program: String,
+    pub frame_rate: u32,
+    pub codec: String,
+    pub pixel_format: String,
+}
+
+impl Default for EncoderConfig {
+    fn default() -> Self {
+        EncoderConfig {
+            program: "ffmpeg".to_string(),
+            frame_rate: 24,
+            codec: "libx264".to_string(),
+            pixel_format: "yuv420p".to_string(),
+        }
+    }
+}
+
+/// Video being encoded by a separate encoder process.
+pub struct VideoEncoder {
+    process: Child,
+    input: ChildStdin,
+    size: [u32; 2],
+}
+
+impl VideoEncoder {
+    /// Starts encoding a video of frames of `size` pixels into `output`,
+    /// which is overwritten.
+    pub fn spawn(config: &EncoderConfig, size: [u32; 2], output: &Path) -> io::Result<Self> {
+        let [width, height] = size;
+        let frame_rate = config.frame_rate.to_string();
+
+        let mut process = Command::new(&config.program)
+            .args(["-loglevel", "error", "-y"])
+            .args(["-f", "rawvideo", "-pix_fmt", "rgba"])
+            .args(["-s", &format!("{}x{}", width, height)])
+            .args(["-framerate", &frame_rate, "-i", "-"])
+            .args(["-c:v", &config.codec, "-pix_fmt", &config.pixel_format])
+            .args(["-r", &frame_rate])
+            .arg(output)
+            .stdin(Stdio::piped())
+            .spawn()?;
+        let input = process.stdin.take().expect("Encoder input is piped");
+
+        Ok(VideoEncoder { process, input, size })
+    }
+
+    pub fn write_frame(&mut self, frame: &RgbaImage) -> io::Result<()> {
+        if frame.dimensions() != (self.size[0], self.size[1]) {
+            let (width, height) = frame.dimensions();
+            let message = format!("Frame of {}x{} in a video of {:?}", width, height, self.size);
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+        }
+
+        self.input.write_all(frame.as_raw())
+    }
+
+    /// Waits for the encoder to write out the video.
+    pub fn finish(self) -> io::Result<()> {
+        let VideoEncoder { mut process, input, .. } = self;
+        drop(input);
+
+        let status = process.wait()?;
+        if !status.success() {
+            let message = format!("Encoder failed with {}", status);
+            return Err(io::Error::other(message));
+        }
+
+        Ok(())
+    }
+}
+
+/// Destination of rendered frames: a video, or numbered PNG files when the
+/// encoder can't be started.
+pub enum FrameWriter {
+    Video(VideoEncoder),
+    Files { folder: PathBuf },
+}
+
+impl FrameWriter {
+    /// Writer encoding the frames into `output`, or saving them into
+    /// `frames_folder` if the encoder is missing.
+    pub fn new(
+        config: &EncoderConfig,
+        size: [u32; 2],
+        output: &Path,
+        frames_folder: PathBuf,
+    ) -> io::Result<Self> {
+        match VideoEncoder::spawn(config, size, output) {
+            Ok(encoder) => Ok(FrameWriter::Video(encoder)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                eprintln!(
+                    "Could not find the encoder `{}`, saving the frames into {} instead",
+                    config.program,
+                    frames_folder.display(),
+                );
+                FrameWriter::files(frames_folder)
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Writer saving the frames into `folder`, which is created if needed.
+    pub fn files(folder: PathBuf) -> io::Result<Self> {
+        fs::create_dir_all(&folder)?;
+        Ok(FrameWriter::Files { folder })
+    }
+
+    /// Writes frame number `index`. Frames must be written in order.
+    pub fn write_frame(&mut self, index: usize, frame: &RgbaImage) -> io::Result<()> {
+        match self {
+            FrameWriter::Video(encoder) => encoder.write_frame(frame),
+            FrameWriter::Files { folder } => frame
+                .save(folder.join(format!("frame_{}.png", index)))
+                .map_err(io::Error::other),
+        }
+    }
+
+    pub fn finish(self) -> io::Result<()> {
+        match self {
+            FrameWriter::Video(encoder) => encoder.finish(),
+            FrameWriter::Files { .. } => Ok(()),
+        }
+    }
+}
+
+/// Encodes the frames `frame_0.png`, `frame_1.png`, ... of `frames_folder`
+/// into `output`. Returns the number of frames.
+pub fn encode_frames(
+    config: &EncoderConfig,
+    frames_folder: &Path,
+    output: &Path,
+) -> io::Result<usize> {
+    let frame_path = |index: usize| frames_folder.join(format!("frame_{}.png", index));
+    let open = |index: usize| {
+        nannou::image::open(frame_path(index))
+            .map(|image| image.to_rgba8())
+            .map_err(io::Error::other)
+    };
+
+    let first = open(0)?;
+    let mut encoder = VideoEncoder::spawn(config, [first.width(), first.height()], output)?;
+    encoder.write_frame(&first)?;
+
+    let mut frame_count = 1;
+    while frame_path(frame_count).exists() {
+        encoder.write_frame(&open(frame_count)?)?;
+        frame_count += 1;
+    }
+
+    encoder.finish()?;
+    Ok(frame_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_writer_saves_frames_without_an_encoder() {
+        let folder = std::env::temp_dir().join("maze_frame_writer_saves_frames_without_an_encoder");
+        let config = EncoderConfig {
+            program: "no-such-encoder".to_string(),
+            ..EncoderConfig::default()
+        };
+
+        let output = folder.join("video.mp4");
+        let mut writer = FrameWriter::new(&config, [2, 2], &output, folder.clone()).unwrap();
+        writer.write_frame(0, &RgbaImage::new(2, 2)).unwrap();
+        writer.write_frame(1, &RgbaImage::new(2, 2)).unwrap();
+        writer.finish().unwrap();
+
+        assert!(folder.join("frame_0.png").exists());
+        assert!(folder.join("frame_1.png").exists());
+        fs::remove_dir_all(folder).unwrap();
+    }
+}